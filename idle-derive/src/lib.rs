@@ -0,0 +1,439 @@
+//! `#[derive(SlackMethod)]`, for defining Slack API methods as structs. It's re-exported by
+//! `idle`, which the generated code refers to, so use it from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parenthesized, parse_macro_input, Data, DeriveInput, Error, Field, Fields, GenericArgument, Ident, LitStr, Path, PathArguments, Result, Token, Type};
+
+/// Define a Slack API method as a struct, with its inputs as the struct's fields. This implements
+/// `idle::methods::Method` and `idle::methods::MethodInputs` for the struct, so it can be sent
+/// with `SlackClient::call`, and adds the method to `idle::registry`.
+///
+/// ```ignore
+/// /// Sends a message to a channel
+/// #[derive(SlackMethod)]
+/// #[slack(path = "chat.postMessage", scopes("chat:write"), ratelimit = Tier4, encoding = Json)]
+/// #[slack(any_of("text", "blocks"), output = PostedMessage)]
+/// pub struct PostMessage {
+///     /// Where to post it
+///     pub channel: ChannelId,
+///     pub text: Option<String>,
+///     pub blocks: Option<serde_json::Value>,
+///     #[slack(default)]
+///     pub unfurl_links: bool,
+/// }
+///
+/// #[derive(Deserialize)]
+/// pub struct PostedMessage {
+///     pub channel: ChannelId,
+///     pub ts: Timestamp,
+/// }
+/// ```
+///
+/// On the struct, `#[slack(...)]` takes:
+/// - `path = "..."`: the API method, which is required
+/// - `scopes("...", ...)`: the scopes a token needs, checked against the ones Slack defines
+/// - `ratelimit = Tier`, `encoding = Json` and `pagination = Cursor`, as for `method_def!`
+/// - `deprecated = "..."`: why the method shouldn't be used any more
+/// - `output = Type`: a struct to deserialize the response into, with a field for each output.
+///   Without one, the method returns `()`
+/// - `any_of("...", ...)` and `one_of("...", ...)`: groups of inputs where at least or exactly
+///   one must be given. Either can be used more than once
+///
+/// Fields that are `Option`s are optional inputs, and the rest are required. On a field,
+/// `#[slack(...)]` takes:
+/// - `rename = "..."`: the name of the input, if it isn't the field's name
+/// - `default`, or `default = "path::to::function"`: a value to send if the input isn't given,
+///   from `Default::default()` or the function, which makes the input optional
+#[proc_macro_derive(SlackMethod, attributes(slack))]
+pub fn derive_slack_method(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct MethodAttrs {
+    path: Option<LitStr>,
+    scopes: Vec<LitStr>,
+    rate_limit: Option<Ident>,
+    encoding: Option<Ident>,
+    pagination: Option<Ident>,
+    deprecated: Option<LitStr>,
+    output: Option<Type>,
+    any_of: Vec<Vec<LitStr>>,
+    one_of: Vec<Vec<LitStr>>,
+}
+
+struct Input {
+    field: Ident,
+    /// The name of the input, which is the field's unless it's renamed
+    name: LitStr,
+    /// The type the input is given as, which is the field's without any `Option`
+    ty: Type,
+    /// Whether the field is an `Option`, so is only an input when it's `Some`
+    is_option: bool,
+    /// Whether the input can be left out, by being an `Option` or having a default
+    optional: bool,
+    default: Option<TokenStream2>,
+}
+
+/// Set an attribute that can only be given once
+fn set_once<T>(slot: &mut Option<T>, value: T, meta: &syn::meta::ParseNestedMeta) -> Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate attribute"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// A parenthesized list of strings, like `("text", "blocks")`
+fn parse_names(input: ParseStream) -> Result<Vec<LitStr>> {
+    let content;
+    parenthesized!(content in input);
+    Ok(Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?.into_iter().collect())
+}
+
+fn parse_method_attrs(input: &DeriveInput) -> Result<MethodAttrs> {
+    let mut attrs = MethodAttrs::default();
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("slack")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                set_once(&mut attrs.path, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("scopes") {
+                attrs.scopes.extend(parse_names(meta.input)?);
+                Ok(())
+            } else if meta.path.is_ident("ratelimit") {
+                set_once(&mut attrs.rate_limit, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("encoding") {
+                set_once(&mut attrs.encoding, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("pagination") {
+                set_once(&mut attrs.pagination, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("deprecated") {
+                set_once(&mut attrs.deprecated, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("output") {
+                set_once(&mut attrs.output, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("any_of") {
+                attrs.any_of.push(parse_names(meta.input)?);
+                Ok(())
+            } else if meta.path.is_ident("one_of") {
+                attrs.one_of.push(parse_names(meta.input)?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown attribute, expected one of `path`, `scopes`, `ratelimit`, `encoding`, `pagination`, `deprecated`, `output`, `any_of` or `one_of`"))
+            }
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+/// The `T` in `Option<T>`, if `ty` is an `Option`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_input(field: &Field) -> Result<Input> {
+    let ident = field.ident.clone().expect("Only named fields are inputs");
+    let mut name = None;
+    let mut default = None;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("slack")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                set_once(&mut name, meta.value()?.parse()?, &meta)
+            } else if meta.path.is_ident("default") {
+                let value = if meta.input.peek(Token![=]) {
+                    let function = meta.value()?.parse::<LitStr>()?.parse::<Path>()?;
+                    quote!(#function())
+                } else {
+                    quote!(::std::default::Default::default())
+                };
+                set_once(&mut default, value, &meta)
+            } else {
+                Err(meta.error("unknown attribute, expected `rename` or `default`"))
+            }
+        })?;
+    }
+
+    let name = name.unwrap_or_else(|| LitStr::new(ident.to_string().trim_start_matches("r#"), ident.span()));
+
+    let (ty, is_option) = match option_inner(&field.ty) {
+        Some(_) if default.is_some() => {
+            return Err(Error::new(field.ty.span(), "`Option` inputs are already optional, so they can't have a default"));
+        }
+        Some(inner) => (inner.clone(), true),
+        None => (field.ty.clone(), false),
+    };
+    let optional = is_option || default.is_some();
+
+    Ok(Input { field: ident, name, ty, is_option, optional, default })
+}
+
+/// A type as written, for the registry
+fn type_string(ty: &Type) -> String {
+    let mut string = ty.to_token_stream().to_string();
+    for (spaced, tight) in [(" < ", "<"), (" <", "<"), (" > ", ">"), (" >", ">"), (" :: ", "::"), (" ,", ",")] {
+        string = string.replace(spaced, tight);
+    }
+    string
+}
+
+/// Check that the names in an `any_of` or `one_of` group are inputs
+fn check_group(group: &[LitStr], inputs: &[Input]) -> Result<()> {
+    for name in group {
+        if !inputs.iter().any(|input| input.name.value() == name.value()) {
+            return Err(Error::new(name.span(), format!("`{}` isn't an input of this method", name.value())));
+        }
+    }
+    Ok(())
+}
+
+fn option_tokens<T: ToTokens>(value: Option<T>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::std::option::Option::Some(#value)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "`SlackMethod` can't be derived for generic structs"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(fields) => {
+                return Err(Error::new(fields.span(), "`SlackMethod` needs named fields, which are the method's inputs"));
+            }
+        },
+        Data::Enum(data) => return Err(Error::new(data.enum_token.span, "`SlackMethod` can only be derived for structs")),
+        Data::Union(data) => return Err(Error::new(data.union_token.span, "`SlackMethod` can only be derived for structs")),
+    };
+
+    let attrs = parse_method_attrs(&input)?;
+    let path = attrs.path.as_ref()
+        .ok_or_else(|| Error::new(ident.span(), "`SlackMethod` needs the method's name, like `#[slack(path = \"chat.postMessage\")]`"))?;
+
+    let inputs = fields.into_iter().map(parse_input).collect::<Result<Vec<_>>>()?;
+    for (i, input) in inputs.iter().enumerate() {
+        if inputs[..i].iter().any(|other| other.name.value() == input.name.value()) {
+            return Err(Error::new(input.name.span(), format!("More than one field is the input `{}`", input.name.value())));
+        }
+    }
+    for group in attrs.any_of.iter().chain(&attrs.one_of) {
+        check_group(group, &inputs)?;
+    }
+
+    let names = inputs.iter().map(|input| &input.name).collect::<Vec<_>>();
+    let tys = inputs.iter().map(|input| &input.ty).collect::<Vec<_>>();
+    let required = inputs.iter().filter(|input| !input.optional).map(|input| &input.name).collect::<Vec<_>>();
+    let defaults = inputs.iter()
+        .filter_map(|input| {
+            let (name, default) = (&input.name, input.default.as_ref()?);
+            Some(quote!(::idle::__private::write_default(&mut request, #name, #default).map_err(invalid)?;))
+        })
+        .collect::<Vec<_>>();
+    let any_of = &attrs.any_of;
+    let one_of = &attrs.one_of;
+
+    let insert_inputs = inputs.iter().map(|input| {
+        let (field, name) = (&input.field, &input.name);
+        if input.is_option {
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#field {
+                    inputs.insert(::std::string::String::from(#name), value as &dyn ::std::any::Any);
+                }
+            }
+        } else {
+            quote!(inputs.insert(::std::string::String::from(#name), &self.#field as &dyn ::std::any::Any);)
+        }
+    });
+
+    // Each scope is checked against the ones Slack defines when the crate is built, pointing at
+    // the scope if it isn't one
+    let scopes = &attrs.scopes;
+    let scope_checks = scopes.iter().map(|scope| {
+        quote_spanned! {scope.span()=>
+            assert!(::idle::scopes::Scope::from_name(#scope).is_some(), concat!("`", #scope, "` isn't a scope Slack defines"));
+        }
+    });
+    let required_scopes = if scopes.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn required_scopes() -> ::std::vec::Vec<::idle::scopes::Scope> {
+                ::std::vec![#(::idle::scopes::Scope::from_name(#scopes).unwrap()),*]
+            }
+        }
+    };
+
+    let rate_limit = attrs.rate_limit.as_ref().map(|tier| quote!(::idle::types::RateLimit::#tier));
+    let rate_limit_fn = rate_limit.as_ref().map(|tier| quote! {
+        fn rate_limit() -> ::std::option::Option<::idle::types::RateLimit> {
+            ::std::option::Option::Some(#tier)
+        }
+    });
+    let encoding = match &attrs.encoding {
+        Some(encoding) => quote!(::idle::transport::Encoding::#encoding),
+        None => quote!(::idle::transport::Encoding::Form),
+    };
+
+    let (output, parse_data) = match &attrs.output {
+        Some(output) => (
+            quote_spanned!(output.span()=> #output),
+            quote!(::idle::__private::parse_struct::<#output>(#path, map)),
+        ),
+        None => (quote!(()), quote!({
+            let _ = map;
+            ::std::result::Result::Ok(())
+        })),
+    };
+
+    let type_strings = tys.iter().map(|ty| type_string(ty));
+    let requireds = inputs.iter().map(|input| !input.optional);
+    let rate_limit = option_tokens(rate_limit);
+    let pagination = option_tokens(attrs.pagination.as_ref().map(|style| quote!(::idle::registry::PaginationStyle::#style)));
+    let deprecated = option_tokens(attrs.deprecated.as_ref());
+
+    Ok(quote! {
+        const _: () = {
+            #(#scope_checks)*
+        };
+
+        impl ::idle::methods::Method for #ident {
+            type Input = Self;
+
+            type Return = #output;
+
+            fn api_str() -> &'static str {
+                #path
+            }
+
+            #required_scopes
+
+            #rate_limit_fn
+
+            fn encoding() -> ::idle::transport::Encoding {
+                #encoding
+            }
+
+            fn write_out(
+                inputs: ::std::collections::HashMap<::std::string::String, &dyn ::std::any::Any>,
+                unknown: ::idle::methods::UnknownInputs,
+            ) -> ::std::result::Result<::idle::transport::ApiRequest, ::idle::types::SlackError> {
+                let invalid = |reason| ::idle::types::SlackError::InvalidInput { method: #path, reason };
+                let mut request = ::idle::transport::ApiRequest::new(#path).with_encoding(#encoding);
+
+                #(::idle::__private::write_input::<#tys>(&mut request, &inputs, #names).map_err(invalid)?;)*
+                #(#defaults)*
+
+                ::idle::__private::handle_unknown_inputs(&mut request, &inputs, &[#(#names),*], unknown)
+                    .map_err(invalid)?;
+                ::idle::__private::check_inputs(
+                    &request,
+                    &[#(#required),*],
+                    &[#(&[#(#any_of),*]),*],
+                    &[#(&[#(#one_of),*]),*],
+                ).map_err(invalid)?;
+
+                ::std::result::Result::Ok(request)
+            }
+
+            fn parse_data(
+                map: &mut ::std::collections::HashMap<::std::string::String, ::idle::__private::serde_json::Value>,
+            ) -> ::std::result::Result<Self::Return, ::idle::types::SlackError> {
+                #parse_data
+            }
+        }
+
+        impl ::idle::methods::MethodInputs for #ident {
+            fn inputs(&self) -> ::std::collections::HashMap<::std::string::String, &dyn ::std::any::Any> {
+                #[allow(unused_mut)]
+                let mut inputs = ::std::collections::HashMap::new();
+                #(#insert_inputs)*
+                inputs
+            }
+        }
+
+        ::idle::__private::inventory::submit! {
+            ::idle::registry::MethodInfo {
+                name: #path,
+                scopes: &[#(#scopes),*],
+                rate_limit: #rate_limit,
+                encoding: #encoding,
+                params: &[#(::idle::registry::ParamInfo { name: #names, ty: #type_strings, required: #requireds }),*],
+                any_of: &[#(&[#(#any_of),*]),*],
+                one_of: &[#(&[#(#one_of),*]),*],
+                pagination: #pagination,
+                deprecated: #deprecated,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn error(input: DeriveInput) -> String {
+        expand(input).expect_err("Expected a compile error").to_string()
+    }
+
+    #[test]
+    fn test_expand() {
+        let expanded = expand(parse_quote! {
+            #[slack(path = "chat.postMessage", scopes("chat:write"), any_of("text", "blocks"))]
+            struct PostMessage {
+                channel: ChannelId,
+                text: Option<String>,
+                blocks: Option<Vec<serde_json::Value>>,
+                #[slack(rename = "thread_ts")]
+                thread: Option<Timestamp>,
+                #[slack(default)]
+                unfurl_links: bool,
+            }
+        }).unwrap().to_string();
+
+        assert!(expanded.contains(r#"ParamInfo { name : "channel" , ty : "ChannelId" , required : true }"#));
+        assert!(expanded.contains(r#"ParamInfo { name : "blocks" , ty : "Vec<serde_json::Value>" , required : false }"#));
+        assert!(expanded.contains(r#"ParamInfo { name : "thread_ts" , ty : "Timestamp" , required : false }"#));
+        assert!(expanded.contains(r#"ParamInfo { name : "unfurl_links" , ty : "bool" , required : false }"#));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error(parse_quote! { struct Nameless { channel: String } }), "`SlackMethod` needs the method's name, like `#[slack(path = \"chat.postMessage\")]`");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b")] enum Enum {} }), "`SlackMethod` can only be derived for structs");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b")] struct Tuple(String); }), "`SlackMethod` needs named fields, which are the method's inputs");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b", scope("chat:write"))] struct Typo; }), "unknown attribute, expected one of `path`, `scopes`, `ratelimit`, `encoding`, `pagination`, `deprecated`, `output`, `any_of` or `one_of`");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b")] #[slack(path = "b.c")] struct Twice; }), "duplicate attribute");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b", one_of("file", "fiel"))] struct Group { file: Option<String> } }), "`fiel` isn't an input of this method");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b")] struct Default { #[slack(default)] limit: Option<u64> } }), "`Option` inputs are already optional, so they can't have a default");
+        assert_eq!(error(parse_quote! { #[slack(path = "a.b")] struct Clash { user: String, #[slack(rename = "user")] user_id: String } }), "More than one field is the input `user`");
+    }
+}
@@ -0,0 +1,281 @@
+//! A synchronous wrapper around [`SlackClient`], for scripts and tools that don't otherwise need
+//! an async runtime. Each [`BlockingSlackClient`] owns a single-threaded tokio runtime and blocks
+//! on the async client's calls, so it takes the same `Method` types and gives the same errors.
+//!
+//! Don't use it from within an async context: blocking on a runtime inside another one panics.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::scopes::{ScopeSet, TokenType};
+use crate::transport::{ApiRequest, HttpTransport};
+use crate::types::*;
+use crate::{cassette, methods, oauth, SlackClient};
+
+pub struct BlockingSlackClient {
+    inner: SlackClient,
+    runtime: Runtime,
+}
+
+impl BlockingSlackClient {
+    pub fn new(token: Token) -> BlockingSlackClient {
+        BlockingSlackClient::from_async(SlackClient::new(token))
+    }
+
+    /// Wrap an async client that's already been set up
+    pub fn from_async(inner: SlackClient) -> BlockingSlackClient {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to start a runtime for the blocking client");
+
+        BlockingSlackClient { inner, runtime }
+    }
+
+    /// Create a client for an installation, holding both the bot and user tokens if there are
+    /// both. Returns `None` if the installation has no usable tokens
+    pub fn from_installation(installation: &oauth::Installation) -> Option<BlockingSlackClient> {
+        SlackClient::from_installation(installation).map(BlockingSlackClient::from_async)
+    }
+
+    /// The async client underneath
+    pub fn as_async(&self) -> &SlackClient {
+        &self.inner
+    }
+
+    fn map(self, builder: impl FnOnce(SlackClient) -> SlackClient) -> BlockingSlackClient {
+        BlockingSlackClient {
+            inner: builder(self.inner),
+            runtime: self.runtime,
+        }
+    }
+
+    /// See [`SlackClient::with_token`]
+    pub fn with_token(self, token: Token) -> BlockingSlackClient {
+        self.map(|inner| inner.with_token(token))
+    }
+
+    /// See [`SlackClient::with_api_url`]
+    pub fn with_api_url(self, api_url: &str) -> BlockingSlackClient {
+        self.map(|inner| inner.with_api_url(api_url))
+    }
+
+    /// See [`SlackClient::with_scope_preflight`]
+    pub fn with_scope_preflight(self) -> BlockingSlackClient {
+        self.map(SlackClient::with_scope_preflight)
+    }
+
+    /// See [`SlackClient::with_strict_parsing`]
+    pub fn with_strict_parsing(self) -> BlockingSlackClient {
+        self.map(SlackClient::with_strict_parsing)
+    }
+
+    /// See [`SlackClient::with_unknown_inputs`]
+    pub fn with_unknown_inputs(self, policy: methods::UnknownInputs) -> BlockingSlackClient {
+        self.map(|inner| inner.with_unknown_inputs(policy))
+    }
+
+    /// See [`SlackClient::with_transport`]
+    pub fn with_transport<T: HttpTransport + 'static>(self, transport: T) -> BlockingSlackClient {
+        self.map(|inner| inner.with_transport(transport))
+    }
+
+    /// See [`SlackClient::with_cassette`]
+    pub fn with_cassette(self, cassette: cassette::Cassette) -> BlockingSlackClient {
+        self.map(|inner| inner.with_cassette(cassette))
+    }
+
+    /// See [`SlackClient::with_token_rotation`]
+    pub fn with_token_rotation<F>(self, oauth: Arc<oauth::OAuthClient>, refresh_token: &str, expires_at: u64, on_refresh: F) -> BlockingSlackClient
+    where
+        F: Fn(&oauth::RefreshedToken) + Send + Sync + 'static
+    {
+        self.map(|inner| inner.with_token_rotation(oauth, refresh_token, expires_at, on_refresh))
+    }
+
+    /// See [`SlackClient::fetch_granted_scopes`]
+    pub fn fetch_granted_scopes(&self) -> Result<(), SlackError> {
+        self.runtime.block_on(self.inner.fetch_granted_scopes())
+    }
+
+    /// See [`SlackClient::granted_scopes`]
+    pub fn granted_scopes(&self, token_type: TokenType) -> Option<ScopeSet> {
+        self.inner.granted_scopes(token_type)
+    }
+
+    /// See [`SlackClient::check`]
+    pub fn check<T: methods::Method>(&self) -> Result<(), SlackError> {
+        self.runtime.block_on(self.inner.check::<T>())
+    }
+
+    pub fn make_request<T: methods::Method>(&self, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.runtime.block_on(self.inner.make_request::<T>(inputs))
+    }
+
+    /// See [`SlackClient::call`]
+    pub fn call<T: methods::MethodInputs>(&self, method: &T) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.runtime.block_on(self.inner.call(method))
+    }
+
+    /// See [`SlackClient::make_request_as`]
+    pub fn make_request_as<T: methods::Method>(&self, token_type: TokenType, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.runtime.block_on(self.inner.make_request_as::<T>(token_type, inputs))
+    }
+
+    /// See [`SlackClient::make_request_all`]
+    pub fn make_request_all<T: methods::Paginated>(&self, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<Vec<T::Item>>, SlackError> {
+        self.runtime.block_on(self.inner.make_request_all::<T>(inputs))
+    }
+
+    /// See [`SlackClient::call_raw`]
+    pub fn call_raw(&self, api_name: &str, params: serde_json::Value) -> Result<SlackResponse<serde_json::Value>, SlackError> {
+        self.runtime.block_on(self.inner.call_raw(api_name, params))
+    }
+
+    /// See [`SlackClient::call_raw_request`]
+    pub fn call_raw_request(&self, request: ApiRequest) -> Result<SlackResponse<serde_json::Value>, SlackError> {
+        self.runtime.block_on(self.inner.call_raw_request(request))
+    }
+
+    /// See [`SlackClient::sync_usergroup_users`]
+    pub fn sync_usergroup_users(&self, usergroup: &UsergroupId, desired: &[UserId]) -> Result<SlackResponse<UsergroupDiff>, SlackError> {
+        self.runtime.block_on(self.inner.sync_usergroup_users(usergroup, desired))
+    }
+
+    /// Download the contents of a file, authenticating with this client's token. Returns the
+    /// number of bytes written
+    pub fn download_file<W: Write>(&self, file: &File, writer: &mut W) -> Result<u64, SlackError> {
+        let url = file.download_url()
+            .ok_or(SlackError::NoDownloadUrl)?;
+
+        self.download_url(url, writer)
+    }
+
+    /// Write the body of a private Slack URL, such as `url_private`, into a writer. Returns the
+    /// number of bytes written
+    pub fn download_url<W: Write>(&self, url: &str, writer: &mut W) -> Result<u64, SlackError> {
+        let mut response = self.runtime.block_on(self.inner.start_download(url))?;
+
+        let mut written = 0;
+        while let Some(chunk) = self.runtime.block_on(response.body.next_chunk())? {
+            writer.write_all(&chunk)?;
+            written += chunk.len() as u64;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+}
+
+impl From<SlackClient> for BlockingSlackClient {
+    fn from(inner: SlackClient) -> BlockingSlackClient {
+        BlockingSlackClient::from_async(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use http::header::HeaderMap;
+    use http::StatusCode;
+    use crate::transport::{BodyStream, BoxFuture, HttpRequest, HttpResponse, StreamingResponse};
+    use crate::Method;
+
+    /// Answers with each body in turn, remembering the URLs it was asked for
+    struct Canned {
+        bodies: Mutex<Vec<&'static str>>,
+        urls: Mutex<Vec<String>>,
+    }
+
+    impl HttpTransport for Arc<Canned> {
+        fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>> {
+            self.urls.lock().unwrap().push(request.url);
+            let body = self.bodies.lock().unwrap().remove(0);
+
+            Box::pin(async move {
+                Ok(HttpResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: body.as_bytes().to_vec(),
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_blocking_client() {
+        let canned = Arc::new(Canned {
+            bodies: Mutex::new(vec![
+                r#"{"ok": true, "files": [], "paging": {"count": 100, "total": 0, "page": 1, "pages": 1}}"#,
+                r#"{"ok": false, "error": "channel_not_found"}"#,
+                "Some notes",
+            ]),
+            urls: Mutex::new(Vec::new()),
+        });
+        let client = BlockingSlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url("https://example.com/api/")
+            .with_transport(canned.clone());
+
+        let files = client.make_request_all::<Method!["files.list"]>(HashMap::new()).unwrap();
+        assert!(files.data().is_empty());
+
+        let channel = ChannelId::new("C12345678");
+        let err = client.make_request::<Method!["conversations.info"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+        ]));
+        assert!(matches!(err, Err(SlackError::ApiError(errors)) if errors == [Error::ChannelNotFound]));
+
+        let mut notes = Vec::new();
+        assert_eq!(client.download_url("https://files.slack.com/files-pri/T1-F1/notes.txt", &mut notes).unwrap(), 10);
+        assert_eq!(notes, b"Some notes");
+
+        assert_eq!(*canned.urls.lock().unwrap(), [
+            "https://example.com/api/files.list",
+            "https://example.com/api/conversations.info",
+            "https://files.slack.com/files-pri/T1-F1/notes.txt",
+        ]);
+    }
+
+    /// Serves a download a few bytes at a time
+    struct Chunked(&'static [u8]);
+
+    struct Chunks(&'static [u8]);
+
+    impl BodyStream for Chunks {
+        fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, SlackError>> {
+            let (chunk, rest) = self.0.split_at(self.0.len().min(4));
+            self.0 = rest;
+            let chunk = if chunk.is_empty() { None } else { Some(chunk.to_vec()) };
+            Box::pin(async { Ok(chunk) })
+        }
+    }
+
+    impl HttpTransport for Chunked {
+        fn send(&self, _: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>> {
+            panic!("Downloads should be streamed")
+        }
+
+        fn send_streaming(&self, _: HttpRequest) -> BoxFuture<'_, Result<StreamingResponse, SlackError>> {
+            let body = Box::new(Chunks(self.0));
+            Box::pin(async {
+                Ok(StreamingResponse { status: StatusCode::OK, headers: HeaderMap::new(), body })
+            })
+        }
+    }
+
+    #[test]
+    fn test_streamed_download() {
+        let client = BlockingSlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_transport(Chunked(b"Some longer notes, in several chunks"));
+
+        let mut notes = Vec::new();
+        let written = client.download_url("https://files.slack.com/files-pri/T1-F1/notes.txt", &mut notes).unwrap();
+        assert_eq!(written, 36);
+        assert_eq!(notes, b"Some longer notes, in several chunks");
+    }
+}
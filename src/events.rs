@@ -0,0 +1,373 @@
+//! Handling what Slack sends to our endpoints: Events API callbacks, slash commands, and
+//! interactions such as block actions and view submissions. [`Payload::parse`] reads a request
+//! body, and a [`Dispatcher`] verifies requests and routes them to handlers.
+
+use std::collections::HashMap;
+
+use http::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+
+use crate::signing::{Verifier, VerifyError};
+use crate::types::{AppId, BotId, ChannelId, TeamId, Timestamp, UserId};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageEvent {
+    pub channel: ChannelId,
+    pub channel_type: Option<String>,
+    pub user: Option<UserId>,
+    pub bot_id: Option<BotId>,
+    pub text: Option<String>,
+    pub subtype: Option<String>,
+    pub ts: Timestamp,
+    pub thread_ts: Option<Timestamp>,
+    pub event_ts: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppMentionEvent {
+    pub channel: ChannelId,
+    pub user: UserId,
+    pub text: String,
+    pub ts: Timestamp,
+    pub thread_ts: Option<Timestamp>,
+    pub event_ts: Option<Timestamp>,
+}
+
+/// What a reaction was added to or removed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionItem {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub channel: Option<ChannelId>,
+    pub ts: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionEvent {
+    pub user: UserId,
+    pub reaction: String,
+    pub item_user: Option<UserId>,
+    pub item: ReactionItem,
+    pub event_ts: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedChannel {
+    pub id: ChannelId,
+    pub name: String,
+    pub created: u64,
+    pub creator: UserId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelCreatedEvent {
+    pub channel: CreatedChannel,
+}
+
+/// The event inside an event callback
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    Message(MessageEvent),
+    AppMention(AppMentionEvent),
+    ReactionAdded(ReactionEvent),
+    ReactionRemoved(ReactionEvent),
+    ChannelCreated(ChannelCreatedEvent),
+    /// An event this crate doesn't model yet
+    Other(Value),
+}
+
+impl Event {
+    /// The event's `type`, such as `message` or `reaction_added`
+    pub fn kind(&self) -> &str {
+        match self {
+            Event::Message(_) => "message",
+            Event::AppMention(_) => "app_mention",
+            Event::ReactionAdded(_) => "reaction_added",
+            Event::ReactionRemoved(_) => "reaction_removed",
+            Event::ChannelCreated(_) => "channel_created",
+            Event::Other(event) => event["type"].as_str().unwrap_or_default(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        fn parse<'de, D: Deserializer<'de>, T: DeserializeOwned>(event: Value) -> Result<T, D::Error> {
+            serde_json::from_value(event).map_err(serde::de::Error::custom)
+        }
+
+        let event = Value::deserialize(de)?;
+        match event["type"].as_str() {
+            Some("message") => parse::<D, _>(event).map(Event::Message),
+            Some("app_mention") => parse::<D, _>(event).map(Event::AppMention),
+            Some("reaction_added") => parse::<D, _>(event).map(Event::ReactionAdded),
+            Some("reaction_removed") => parse::<D, _>(event).map(Event::ReactionRemoved),
+            Some("channel_created") => parse::<D, _>(event).map(Event::ChannelCreated),
+            _ => Ok(Event::Other(event)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventCallback {
+    pub team_id: TeamId,
+    pub api_app_id: AppId,
+    pub event_id: String,
+    pub event_time: u64,
+    pub event: Event,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlashCommand {
+    pub command: String,
+    pub text: String,
+    pub user_id: UserId,
+    pub user_name: Option<String>,
+    pub channel_id: ChannelId,
+    pub channel_name: Option<String>,
+    pub team_id: TeamId,
+    pub team_domain: Option<String>,
+    pub api_app_id: Option<AppId>,
+    pub response_url: String, // TODO: Actually URI
+    pub trigger_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionUser {
+    pub id: UserId,
+    pub username: Option<String>,
+    pub name: Option<String>,
+    pub team_id: Option<TeamId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionTeam {
+    pub id: TeamId,
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionChannel {
+    pub id: ChannelId,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockAction {
+    pub action_id: String,
+    pub block_id: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: Option<String>,
+    pub action_ts: Option<Timestamp>,
+}
+
+/// A user clicked a button or otherwise used an interactive block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockActions {
+    pub user: InteractionUser,
+    pub team: Option<InteractionTeam>,
+    pub api_app_id: Option<AppId>,
+    pub channel: Option<InteractionChannel>,
+    pub trigger_id: Option<String>,
+    pub response_url: Option<String>, // TODO: Actually URI
+    pub message: Option<Value>,
+    pub actions: Vec<BlockAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewState {
+    /// Input values, keyed by block ID then action ID
+    pub values: HashMap<String, HashMap<String, Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct View {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub callback_id: String,
+    pub private_metadata: Option<String>,
+    pub hash: Option<String>,
+    pub state: Option<ViewState>,
+}
+
+/// A user submitted a modal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewSubmission {
+    pub user: InteractionUser,
+    pub team: Option<InteractionTeam>,
+    pub api_app_id: Option<AppId>,
+    pub trigger_id: Option<String>,
+    pub view: View,
+}
+
+/// Anything Slack sends to an app's request URLs
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Payload {
+    /// Slack checking the Events API request URL, which must be answered with the challenge
+    UrlVerification { challenge: String },
+    EventCallback(EventCallback),
+    SlashCommand(SlashCommand),
+    BlockActions(BlockActions),
+    ViewSubmission(ViewSubmission),
+    /// A payload this crate doesn't model yet
+    Other(Value),
+}
+
+impl Payload {
+    /// Parse a request body. Events come as JSON, slash commands as form fields, and
+    /// interactions as JSON in the `payload` form field
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Payload, serde_json::Error> {
+        if !content_type.starts_with("application/x-www-form-urlencoded") {
+            return Payload::from_json(serde_json::from_slice(body)?);
+        }
+
+        let fields = form_urlencoded::parse(body)
+            .into_owned()
+            .collect::<HashMap<String, String>>();
+
+        match fields.get("payload") {
+            Some(payload) => Payload::from_json(serde_json::from_str(payload)?),
+            None if fields.contains_key("command") => serde_json::to_value(fields)
+                .and_then(serde_json::from_value)
+                .map(Payload::SlashCommand),
+            None => Ok(Payload::Other(json!(fields))),
+        }
+    }
+
+    fn from_json(payload: Value) -> Result<Payload, serde_json::Error> {
+        match payload["type"].as_str() {
+            Some("url_verification") => Ok(Payload::UrlVerification {
+                challenge: payload["challenge"].as_str().unwrap_or_default().to_string(),
+            }),
+            Some("event_callback") => serde_json::from_value(payload).map(Payload::EventCallback),
+            Some("block_actions") => serde_json::from_value(payload).map(Payload::BlockActions),
+            Some("view_submission") => serde_json::from_value(payload).map(Payload::ViewSubmission),
+            _ => Ok(Payload::Other(payload)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DispatchError {
+    /// The request isn't signed by Slack, and should be answered with a 401
+    Unverified(VerifyError),
+    /// The request is signed, but couldn't be parsed
+    Malformed(serde_json::Error),
+}
+
+type EventHandler = Box<dyn Fn(&EventCallback) + Send + Sync>;
+type CommandHandler = Box<dyn Fn(&SlashCommand) -> Option<Value> + Send + Sync>;
+type ActionHandler = Box<dyn Fn(&BlockActions, &BlockAction) + Send + Sync>;
+type ViewHandler = Box<dyn Fn(&ViewSubmission) -> Option<Value> + Send + Sync>;
+
+/// Verifies incoming requests and routes them to the handlers registered for them. Slack wants
+/// an answer within three seconds, so handlers should hand long-running work off to a task and
+/// return straight away
+pub struct Dispatcher {
+    verifier: Verifier,
+    events: HashMap<String, Vec<EventHandler>>,
+    commands: HashMap<String, CommandHandler>,
+    actions: HashMap<String, ActionHandler>,
+    views: HashMap<String, ViewHandler>,
+}
+
+impl Dispatcher {
+    pub fn new(verifier: Verifier) -> Dispatcher {
+        Dispatcher {
+            verifier,
+            events: HashMap::new(),
+            commands: HashMap::new(),
+            actions: HashMap::new(),
+            views: HashMap::new(),
+        }
+    }
+
+    /// Handle events of a `type`, such as `message`. Every handler for the type is called
+    pub fn on_event<F>(mut self, kind: &str, handler: F) -> Dispatcher
+    where
+        F: Fn(&EventCallback) + Send + Sync + 'static
+    {
+        self.events.entry(kind.to_string()).or_default().push(Box::new(handler));
+        self
+    }
+
+    /// Handle a slash command, such as `/deploy`. The handler can return a message to reply with
+    pub fn on_command<F>(mut self, command: &str, handler: F) -> Dispatcher
+    where
+        F: Fn(&SlashCommand) -> Option<Value> + Send + Sync + 'static
+    {
+        self.commands.insert(command.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Handle block actions with an `action_id`
+    pub fn on_action<F>(mut self, action_id: &str, handler: F) -> Dispatcher
+    where
+        F: Fn(&BlockActions, &BlockAction) + Send + Sync + 'static
+    {
+        self.actions.insert(action_id.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Handle submissions of views with a `callback_id`. The handler can return a
+    /// `response_action`, such as errors to show on the view
+    pub fn on_view<F>(mut self, callback_id: &str, handler: F) -> Dispatcher
+    where
+        F: Fn(&ViewSubmission) -> Option<Value> + Send + Sync + 'static
+    {
+        self.views.insert(callback_id.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Verify and handle a request, returning the JSON to respond with, if any. Requests nothing
+    /// is registered for are acknowledged with an empty response
+    pub fn dispatch(&self, headers: &HeaderMap, body: &[u8]) -> Result<Option<Value>, DispatchError> {
+        self.verifier
+            .verify_headers(headers, body)
+            .map_err(DispatchError::Unverified)?;
+
+        let content_type = headers.get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let payload = Payload::parse(content_type, body).map_err(DispatchError::Malformed)?;
+
+        Ok(self.dispatch_payload(&payload))
+    }
+
+    /// Handle a payload that's already been verified and parsed
+    pub fn dispatch_payload(&self, payload: &Payload) -> Option<Value> {
+        match payload {
+            Payload::UrlVerification { challenge } => Some(json!({ "challenge": challenge })),
+            Payload::EventCallback(callback) => {
+                for handler in self.events.get(callback.event.kind()).into_iter().flatten() {
+                    handler(callback);
+                }
+                None
+            }
+            Payload::SlashCommand(command) => self.commands
+                .get(&command.command)
+                .and_then(|handler| handler(command)),
+            Payload::BlockActions(actions) => {
+                for action in &actions.actions {
+                    if let Some(handler) = self.actions.get(&action.action_id) {
+                        handler(actions, action);
+                    }
+                }
+                None
+            }
+            Payload::ViewSubmission(submission) => self.views
+                .get(&submission.view.callback_id)
+                .and_then(|handler| handler(submission)),
+            Payload::Other(_) => None,
+        }
+    }
+}
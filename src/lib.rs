@@ -1,82 +1,1021 @@
-
 use std::collections::HashMap;
 use std::any::Any;
+use std::sync::Arc;
 
-use reqwest::Client as ReqwestClient;
+use http::{Method as HttpMethod, StatusCode};
+use reqwest::Url;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 pub mod types;
 pub mod scopes;
 pub mod methods;
+pub mod registry;
+pub mod oauth;
+pub mod store;
+pub mod transport;
+pub mod cassette;
+pub mod signing;
+pub mod events;
+mod rotation;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
+#[cfg(feature = "derive")]
+pub use idle_derive::SlackMethod;
+
+// Lets `#[derive(SlackMethod)]` name this crate as `::idle` inside it too
+extern crate self as idle;
+
+// What the code `#[derive(SlackMethod)]` generates uses. Not public API
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+    pub use serde_json;
+    pub use crate::methods::{check_inputs, handle_unknown_inputs, parse_struct, write_default, write_input};
+}
 
+use scopes::{Scope, ScopeSet, TokenType};
+use transport::{ApiRequest, HttpRequest, HttpTransport, ReqwestTransport, StreamingResponse};
 use types::*;
 
 pub struct SlackClient {
-    token: String,
-    req_client: ReqwestClient,
+    tokens: Vec<rotation::TokenSlot>,
+    api_url: String,
+    scope_preflight: bool,
+    strict_parsing: bool,
+    unknown_inputs: methods::UnknownInputs,
+    cassette: Option<cassette::Cassette>,
+    transport: Arc<dyn HttpTransport>,
 }
 
 impl SlackClient {
     const URL_BASE: &'static str = "https://slack.com/api/";
+    const FILE_HOST: &'static str = "files.slack.com";
+
+    pub fn new(token: Token) -> SlackClient {
+        Self::with_shared_transport(token, Arc::new(ReqwestTransport::new()))
+    }
 
-    pub fn new(token: &str) -> SlackClient {
+    fn with_shared_transport(token: Token, transport: Arc<dyn HttpTransport>) -> SlackClient {
         SlackClient {
-            token: token.to_string(),
-            req_client: ReqwestClient::new(),
+            tokens: vec![rotation::TokenSlot::new(token)],
+            api_url: Self::URL_BASE.to_string(),
+            scope_preflight: false,
+            strict_parsing: false,
+            unknown_inputs: methods::UnknownInputs::default(),
+            cassette: None,
+            transport,
+        }
+    }
+
+    /// Hold another token, such as a user token alongside a bot token. Each request picks
+    /// whichever token can be used with the method's scopes, preferring bot tokens
+    pub fn with_token(mut self, token: Token) -> SlackClient {
+        self.tokens.push(rotation::TokenSlot::new(token));
+        self
+    }
+
+    /// Send API calls somewhere other than `https://slack.com/api/`, such as a local stand-in
+    pub fn with_api_url(mut self, api_url: &str) -> SlackClient {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Check every request's scopes against the scopes the token was last seen to have, failing
+    /// locally when it has none of them with [`SlackError::MissingScopes`] instead of making a doomed call. Granted
+    /// scopes are learnt from every response, or up front with [`SlackClient::fetch_granted_scopes`]
+    pub fn with_scope_preflight(mut self) -> SlackClient {
+        self.scope_preflight = true;
+        self
+    }
+
+    /// Fail with [`SlackError::UnknownFields`] when a response has fields the models don't know
+    /// about, rather than keeping them in `extra`. Slack adds fields all the time, so this is only
+    /// meant for tests that check the models against the real API. Types that aren't modelled yet,
+    /// like `Block` and `Attachment`, keep all their fields in `extra` and aren't checked
+    pub fn with_strict_parsing(mut self) -> SlackClient {
+        self.strict_parsing = true;
+        self
+    }
+
+    /// Choose what happens to inputs a method doesn't define. By default they're rejected with
+    /// [`InputError::Unknown`], but they can be forwarded to use parameters Slack has added
+    /// before this crate knows about them
+    pub fn with_unknown_inputs(mut self, policy: methods::UnknownInputs) -> SlackClient {
+        self.unknown_inputs = policy;
+        self
+    }
+
+    /// Send requests with something other than `reqwest`, such as another HTTP client or a test
+    /// double
+    pub fn with_transport<T: HttpTransport + 'static>(mut self, transport: T) -> SlackClient {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Record requests to a cassette, or answer them from one, depending on its mode
+    pub fn with_cassette(mut self, cassette: cassette::Cassette) -> SlackClient {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Enable token rotation for the most recently added token. The token will be refreshed
+    /// shortly before `expires_at` (in seconds since the Unix epoch), or whenever Slack says it's
+    /// expired. Refresh tokens are single use, so `on_refresh` should persist the new tokens it's
+    /// given
+    pub fn with_token_rotation<F>(mut self, oauth: Arc<oauth::OAuthClient>, refresh_token: &str, expires_at: u64, on_refresh: F) -> SlackClient
+    where
+        F: Fn(&oauth::RefreshedToken) + Send + Sync + 'static
+    {
+        let slot = self.tokens.last_mut().expect("Clients always have a token");
+        slot.rotation = Some(rotation::TokenRotation::new(oauth, refresh_token, expires_at, Box::new(on_refresh)));
+        self
+    }
+
+    /// Create a client for an installation, holding both the bot and user tokens if there are
+    /// both. Returns `None` if the installation has no usable tokens
+    pub fn from_installation(installation: &oauth::Installation) -> Option<SlackClient> {
+        let mut tokens = installation.bot
+            .as_ref()
+            .map(|bot| &bot.token)
+            .into_iter()
+            .chain(installation.user.token.as_ref())
+            .filter_map(|token| Token::parse(token));
+
+        let client = SlackClient::new(tokens.next()?);
+        Some(tokens.fold(client, SlackClient::with_token))
+    }
+
+    /// Pick the token to use for a method. If `token_type` is given only that kind of token is
+    /// considered, otherwise bot tokens are preferred, then user tokens, then anything else
+    fn select_token(&self, method: &'static str, scopes: &[Scope], token_type: Option<TokenType>) -> Result<&rotation::TokenSlot, SlackError> {
+        let mut candidates = self.tokens
+            .iter()
+            .filter(|slot| token_type.is_none_or(|ty| slot.token_type() == Some(ty)))
+            .collect::<Vec<_>>();
+
+        candidates.sort_by_key(|slot| match slot.token_type() {
+            Some(TokenType::Bot) => 0,
+            Some(TokenType::User) => 1,
+            Some(TokenType::App) => 2,
+            None => 3,
+        });
+
+        let mut disallowed = None;
+        for slot in candidates {
+            match slot.token().check_scopes(scopes) {
+                Ok(()) => return Ok(slot),
+                Err(scope) => {
+                    disallowed.get_or_insert(scope);
+                }
+            }
+        }
+
+        match (disallowed, token_type) {
+            (Some(scope), _) => Err(SlackError::NotAllowedTokenType { method, scope }),
+            (None, Some(token_type)) => Err(SlackError::MissingToken(token_type)),
+            (None, None) => unreachable!("Clients always have a token"),
+        }
+    }
+
+    /// Check the scopes a token is known to have against a method's, any one of which is enough
+    fn preflight(slot: &rotation::TokenSlot, method: &'static str, scopes: &[Scope]) -> Result<(), SlackError> {
+        let granted = match slot.granted_scopes() {
+            Some(granted) => granted,
+            None => return Ok(()),
+        };
+        if scopes.is_empty() || scopes.iter().any(|scope| granted.contains(scope)) {
+            return Ok(());
+        }
+
+        // Only suggest the scopes this kind of token could be granted
+        let token_type = slot.token_type();
+        let missing = scopes.iter()
+            .filter(|scope| token_type.is_none_or(|ty| scope.token_types().contains(&ty)))
+            .copied()
+            .collect();
+        Err(SlackError::MissingScopes { method, missing })
+    }
+
+    /// Ask Slack which scopes each token has been granted, by calling `auth.test` with it
+    pub async fn fetch_granted_scopes(&self) -> Result<(), SlackError> {
+        for slot in &self.tokens {
+            self.fetch_slot_scopes(slot).await?;
+        }
+        Ok(())
+    }
+
+    async fn fetch_slot_scopes(&self, slot: &rotation::TokenSlot) -> Result<(), SlackError> {
+        let token = slot.fresh_token().await?;
+        self.send(slot, &token, ApiRequest::new("auth.test")).await?;
+        Ok(())
+    }
+
+    /// The scopes a kind of token was last seen to have, if it's been used yet
+    pub fn granted_scopes(&self, token_type: TokenType) -> Option<ScopeSet> {
+        self.tokens
+            .iter()
+            .find(|slot| slot.token_type() == Some(token_type))
+            .and_then(rotation::TokenSlot::granted_scopes)
+    }
+
+    /// Check the token that would be used for a method has been granted one of its scopes,
+    /// calling `auth.test` first if the token's scopes aren't known yet
+    pub async fn check<T: methods::Method>(&self) -> Result<(), SlackError> {
+        let required = T::required_scopes();
+        let slot = self.select_token(T::api_str(), &required, None)?;
+
+        if slot.granted_scopes().is_none() {
+            self.fetch_slot_scopes(slot).await?;
+        }
+
+        Self::preflight(slot, T::api_str(), &required)
+    }
+
+    pub async fn make_request<T: methods::Method>(&self, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.make_request_inner::<T>(None, inputs).await
+    }
+
+    /// Call a method whose inputs are its own fields, as defined with `#[derive(SlackMethod)]`
+    pub async fn call<T: methods::MethodInputs>(&self, method: &T) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.make_request::<T>(method.inputs()).await
+    }
+
+    /// Make a request with a specific kind of token, rather than letting the client choose
+    pub async fn make_request_as<T: methods::Method>(&self, token_type: TokenType, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
+        self.make_request_inner::<T>(Some(token_type), inputs).await
+    }
+
+    async fn make_request_inner<T: methods::Method>(&self, token_type: Option<TokenType>, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
+        let request = T::write_out(inputs, self.unknown_inputs)?;
+
+        // No point making the round trip if Slack is only going to reject the token
+        let required = T::required_scopes();
+        let slot = self.select_token(T::api_str(), &required, token_type)?;
+        if self.scope_preflight {
+            Self::preflight(slot, T::api_str(), &required)?;
         }
+        let token = slot.fresh_token().await?;
+
+        let raw_response = self.send_refreshing(slot, token, request).await?;
+        let mut fields = raw_response.other;
+
+        let data = if self.strict_parsing {
+            let (data, mut unknown) = collect_unknown_fields(|| T::parse_data(&mut fields));
+            let data = data?;
+            unknown.extend(fields.keys().cloned());
+
+            if !unknown.is_empty() {
+                unknown.sort();
+                return Err(SlackError::UnknownFields { method: T::api_str(), fields: unknown });
+            }
+            data
+        } else {
+            T::parse_data(&mut fields)?
+        };
+
+        Ok(SlackResponse {
+            data,
+            warnings: raw_response.warnings.map(Warning::from_str),
+            extra: fields.into_iter().collect(),
+        })
     }
 
-    async fn make_request<T: methods::Method>(&self, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<T::Return>, SlackError> {
-        let url = Self::URL_BASE.to_string() + T::api_str();
+    /// Call any API method by name, including ones this crate doesn't model yet. `params` should
+    /// be a JSON object, and is sent form encoded; the body Slack gives back is returned as is,
+    /// without `ok`, `warning` or `error`
+    pub async fn call_raw(&self, api_name: &str, params: serde_json::Value) -> Result<SlackResponse<serde_json::Value>, SlackError> {
+        let fields = serde_json::from_value(params)?;
+        let request = ApiRequest {
+            fields,
+            ..ApiRequest::new(api_name)
+        };
 
-        let request = self.req_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.token));
+        self.call_raw_request(request).await
+    }
+
+    /// Like [`SlackClient::call_raw`], for requests that need to be sent as JSON or with files
+    pub async fn call_raw_request(&self, request: ApiRequest) -> Result<SlackResponse<serde_json::Value>, SlackError> {
+        // The scopes of unmodelled methods aren't known, so leave it to Slack to check them
+        let slot = self.select_token("call_raw", &[], None)?;
+        let token = slot.fresh_token().await?;
+
+        let raw_response = self.send_refreshing(slot, token, request).await?;
+
+        Ok(SlackResponse {
+            data: serde_json::Value::Object(raw_response.other.into_iter().collect()),
+            warnings: raw_response.warnings.map(Warning::from_str),
+            extra: serde_json::Map::new(),
+        })
+    }
+
+    async fn send_refreshing(&self, slot: &rotation::TokenSlot, token: Token, request: ApiRequest) -> Result<RawResponse, SlackError> {
+        match self.send(slot, &token, request.clone()).await {
+            Err(SlackError::ApiError(errors)) if errors.iter().any(|err| matches!(err, Error::TokenExpired)) => {
+                // Our idea of the expiry time was off, so refresh now and try once more
+                if slot.refresh_expired(&token).await? {
+                    self.send(slot, &slot.token(), request).await
+                } else {
+                    Err(SlackError::ApiError(errors))
+                }
+            }
+            result => result,
+        }
+    }
 
-        let request = T::write_out(request, inputs);
+    /// Send a request to an API method, returning the response if Slack says it succeeded
+    async fn send(&self, slot: &rotation::TokenSlot, token: &Token, request: ApiRequest) -> Result<RawResponse, SlackError> {
+        let http_request = request.to_http(&self.api_url, Some(token.as_str()))?;
 
-        // TODO: What if it's gzipped or not JSON?
-        let raw_response: RawResponse = request
-            .send()
-            .await?
-            .json()
-            .await
-            .unwrap();
+        let response = match &self.cassette {
+            Some(cassette) => cassette.send(&*self.transport, &request, http_request).await?,
+            None => self.transport.send(http_request).await?,
+        };
+
+        slot.record_granted_scopes(&response.headers);
+
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response.headers
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+            return Err(SlackError::RateLimited { retry_after });
+        }
+
+        // TODO: What if it's gzipped?
+        let raw_response: RawResponse = serde_json::from_slice(&response.body)?;
 
         if raw_response.ok {
-            let response = SlackResponse {
-                data: T::parse_data(raw_response.other),
-                warnings: raw_response.warnings.map(Warning::from_str)
-            };
-            Ok(response)
+            Ok(raw_response)
         } else {
-            let response = SlackError::ApiError(Error::from_str(raw_response.error.unwrap()));
+            let response = SlackError::ApiError(Error::from_str(raw_response.error.unwrap_or_default()));
             Err(response)
         }
     }
+
+    /// Make a request to a paginated method, following cursors or page numbers until every page
+    /// has been fetched, and collect the results
+    pub async fn make_request_all<T: methods::Paginated>(&self, inputs: HashMap<String, &dyn Any>) -> Result<SlackResponse<Vec<T::Item>>, SlackError> {
+        let mut items = Vec::new();
+        let mut warnings: Option<Vec<Warning>> = None;
+        let mut extra = serde_json::Map::new();
+        let mut next = None;
+
+        loop {
+            let mut page_inputs = inputs.clone();
+            match &next {
+                Some(methods::NextPage::Cursor(cursor)) => {
+                    page_inputs.insert(String::from("cursor"), cursor as &dyn Any);
+                }
+                Some(methods::NextPage::Page(page)) => {
+                    page_inputs.insert(String::from("page"), page as &dyn Any);
+                }
+                None => (),
+            }
+
+            let response = self.make_request::<T>(page_inputs).await?;
+            if let Some(page_warnings) = response.warnings {
+                warnings.get_or_insert_with(Vec::new).extend(page_warnings);
+            }
+            extra.extend(response.extra);
+
+            next = T::next_page(&response.data);
+            items.extend(T::into_items(response.data));
+
+            if next.is_none() {
+                break;
+            }
+        }
+
+        Ok(SlackResponse { data: items, warnings, extra })
+    }
+
+    /// Bring a user group's members in line with `desired`, only calling `usergroups.users.update`
+    /// if something actually changed. Slack doesn't allow a group to have no members, so an empty
+    /// `desired` will be rejected by the API; disable the group instead
+    pub async fn sync_usergroup_users(&self, usergroup: &UsergroupId, desired: &[UserId]) -> Result<SlackResponse<UsergroupDiff>, SlackError> {
+        let current = self.make_request::<Method!["usergroups.users.list"]>(
+            HashMap::from([
+                (String::from("usergroup"), usergroup as &dyn Any),
+            ])
+        ).await?;
+
+        let diff = UsergroupDiff::between(current.data(), desired);
+        let mut warnings = current.warnings;
+        let mut extra = current.extra;
+
+        if !diff.is_empty() {
+            let users = desired.iter()
+                .map(UserId::as_str)
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let update = self.make_request::<Method!["usergroups.users.update"]>(
+                HashMap::from([
+                    (String::from("usergroup"), usergroup as &dyn Any),
+                    (String::from("users"), &users as &dyn Any),
+                ])
+            ).await?;
+
+            if let Some(update_warnings) = update.warnings {
+                warnings.get_or_insert_with(Vec::new).extend(update_warnings);
+            }
+            extra.extend(update.extra);
+        }
+
+        Ok(SlackResponse { data: diff, warnings, extra })
+    }
+
+    /// Download the contents of a file, authenticating with this client's token. Returns the
+    /// number of bytes written
+    pub async fn download_file<W: AsyncWrite + Unpin>(&self, file: &File, writer: &mut W) -> Result<u64, SlackError> {
+        let url = file.download_url()
+            .ok_or(SlackError::NoDownloadUrl)?;
+
+        self.download_url(url, writer).await
+    }
+
+    /// Stream the body of a private Slack URL, such as `url_private`, into a writer. Returns the
+    /// number of bytes written. The token is only sent to `https://files.slack.com` or the host
+    /// API calls go to, so other URLs are refused with [`SlackError::UntrustedUrl`]
+    pub async fn download_url<W: AsyncWrite + Unpin>(&self, url: &str, writer: &mut W) -> Result<u64, SlackError> {
+        let mut response = self.start_download(url).await?;
+
+        let mut written = 0;
+        while let Some(chunk) = response.body.next_chunk().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+
+        Ok(written)
+    }
+
+    /// Send the request for a download, leaving its body to be read a chunk at a time
+    pub(crate) async fn start_download(&self, url: &str) -> Result<StreamingResponse, SlackError> {
+        if !self.is_trusted_url(url) {
+            return Err(SlackError::UntrustedUrl(url.to_string()));
+        }
+
+        let token = self.select_token("files.download", &[], None)?
+            .fresh_token()
+            .await?;
+
+        let request = HttpRequest::new(HttpMethod::GET, url)
+            .with_bearer(token.as_str())?;
+        let response = self.transport.send_streaming(request).await?;
+        if !response.status.is_success() {
+            return Err(SlackError::HttpStatus(response.status.as_u16()));
+        }
+
+        Ok(response)
+    }
+
+    /// Whether `url` is somewhere the token can safely be sent
+    fn is_trusted_url(&self, url: &str) -> bool {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+
+        let is_file_host = url.scheme() == "https" && url.host_str() == Some(Self::FILE_HOST);
+        let is_api_host = Url::parse(&self.api_url).is_ok_and(|api_url| api_url.origin() == url.origin());
+        is_file_host || is_api_host
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
+    use serde_json::json;
+    use crate::mock::{MockResponse, MockServer};
+
+    fn refreshed_json() -> serde_json::Value {
+        json!({
+            "app_id": "A0KRD7HC3",
+            "access_token": "xoxb-new",
+            "token_type": "bot",
+            "refresh_token": "xoxe-1-new",
+            "expires_in": 43200,
+        })
+    }
+
+    fn empty_list() -> serde_json::Value {
+        json!({ "channels": [], "response_metadata": { "next_cursor": "" } })
+    }
+
+    fn rotating_client(api_url: &str, expires_at: u64, refreshed: Arc<Mutex<Vec<oauth::RefreshedToken>>>) -> SlackClient {
+        let oauth = oauth::OAuthClient::new("123.456", "secret")
+            .with_api_url(api_url);
+
+        SlackClient::new(Token::parse("xoxb-old").unwrap())
+            .with_api_url(api_url)
+            .with_token_rotation(Arc::new(oauth), "xoxe-1-old", expires_at, move |token| {
+                refreshed.lock().unwrap().push(token.clone())
+            })
+    }
 
     #[tokio::test]
     async fn test_basics() {
-        let client = SlackClient::new(
-            env!("SLACK_TOKEN")
-        );
-
-        println!(
-            "{:#?}",
-            client.make_request::<Method!["conversations.list"]>(HashMap::new()).await,
-        );
-
-        println!(
-            "{:#?}",
-            client.make_request::<Method!["conversations.list"]>(
-                HashMap::from([
-                    (String::from("exclude_archived"), &true as &dyn std::any::Any),
-                ])
-            ).await,
-        )
+        let server = MockServer::start();
+        server.respond_ok("conversations.list", empty_list());
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let response = client.make_request::<Method!["conversations.list"]>(
+            HashMap::from([
+                (String::from("exclude_archived"), &true as &dyn std::any::Any),
+            ])
+        ).await.unwrap();
+
+        assert!(response.data().0.is_empty());
+        assert!(response.warnings().is_empty());
+
+        let request = server.last_request("conversations.list");
+        assert_eq!(request.token(), Some("xoxb-1234"));
+        request.assert_fields(&[("exclude_archived", "true")]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_warnings() {
+        let server = MockServer::start();
+        server.respond("conversations.list", MockResponse::error("channel_not_found"))
+            .respond("conversations.list", MockResponse::ok(empty_list()).with_warning("missing_charset"))
+            .respond("conversations.list", MockResponse::rate_limited(30))
+            .respond("conversations.list", MockResponse::raw(502, "<html>Bad Gateway</html>"))
+            .respond("conversations.list", MockResponse::raw(200, r#"{"ok": false}"#));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::ApiError(errors)) if matches!(errors[..], [Error::ChannelNotFound])));
+
+        let response = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+        assert_eq!(response.warnings(), [Warning::MissingCharset]);
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::RateLimited { retry_after: Some(30) })));
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::JsonError(_))));
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::ApiError(errors)) if errors == [Error::Unknown(String::new())]));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_inputs() {
+        let server = MockServer::start();
+        server.respond_ok("conversations.list", empty_list());
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+        let inputs = || HashMap::from([
+            (String::from("exclude_archived"), &true as &dyn Any),
+            (String::from("include_new"), &"yes" as &dyn Any),
+        ]);
+
+        let result = client.make_request::<Method!["conversations.list"]>(inputs()).await;
+        assert!(matches!(result, Err(SlackError::InvalidInput { reason: InputError::Unknown(_), .. })));
+        assert!(server.requests().is_empty());
+
+        let client = client.with_unknown_inputs(methods::UnknownInputs::Forward);
+        client.make_request::<Method!["conversations.list"]>(inputs()).await.unwrap();
+        server.last_request("conversations.list").assert_fields(&[("exclude_archived", "true"), ("include_new", "yes")]);
+    }
+
+    #[tokio::test]
+    async fn test_json_encoding() {
+        let server = MockServer::start();
+        server.respond_ok("chat.postMessage", json!({
+            "channel": "C12345678",
+            "ts": "1610000000.000100",
+            "message": { "type": "message", "text": "", "ts": "1610000000.000100" },
+        }));
+        server.respond_ok("views.open", json!({ "view": { "id": "V12345678", "type": "modal", "callback_id": "deploy" } }));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let channel = ChannelId::new("C12345678");
+        let blocks = json!([{ "type": "section", "text": { "type": "mrkdwn", "text": "*Deployed*" } }]);
+        client.make_request::<Method!["chat.postMessage"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("blocks"), &blocks as &dyn Any),
+        ])).await.unwrap();
+
+        let request = server.last_request("chat.postMessage");
+        assert_eq!(request.headers["content-type"], "application/json; charset=utf-8");
+        assert_eq!(request.token(), Some("xoxb-1234"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(request.field("blocks").unwrap()).unwrap(), blocks);
+
+        let view = json!({ "type": "modal", "callback_id": "deploy", "title": { "type": "plain_text", "text": "Deploy" }, "blocks": [] });
+        let trigger_id = String::from("12345.98765.abcd2358fdea");
+        let opened = client.make_request::<Method!["views.open"]>(HashMap::from([
+            (String::from("view"), &view as &dyn Any),
+            (String::from("trigger_id"), &trigger_id as &dyn Any),
+        ])).await.unwrap();
+        assert_eq!(opened.data().id, "V12345678");
+        assert_eq!(server.last_request("views.open").headers["content-type"], "application/json; charset=utf-8");
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn test_derived_method() {
+        use serde::Deserialize;
+        use crate::methods::{Method, MethodInputs};
+
+        /// Reminds a user about something
+        #[derive(SlackMethod)]
+        #[slack(path = "reminders.add", scopes("reminders:write"), ratelimit = Tier2, output = Added)]
+        struct RemindersAdd {
+            text: String,
+            time: String,
+            #[slack(rename = "user")]
+            for_user: Option<UserId>,
+            #[slack(default = "default_team")]
+            team_id: TeamId,
+        }
+
+        fn default_team() -> TeamId {
+            TeamId::new("T12345678")
+        }
+
+        #[derive(Deserialize)]
+        struct Added {
+            reminder: Reminder,
+        }
+
+        #[derive(Deserialize)]
+        struct Reminder {
+            id: String,
+            #[serde(rename = "recurring")]
+            repeats: bool,
+        }
+
+        let server = MockServer::start();
+        server.respond_ok("reminders.add", json!({
+            "reminder": { "id": "Rm12345678", "text": "Deploy", "recurring": false },
+            "extra": "field",
+        }));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let reminder = RemindersAdd {
+            text: String::from("Deploy"),
+            time: String::from("in 5 minutes"),
+            for_user: Some(UserId::new("U12345678")),
+            team_id: default_team(),
+        };
+        let added = client.call(&reminder).await.unwrap();
+        assert_eq!(added.data().reminder.id, "Rm12345678");
+        assert!(!added.data().reminder.repeats);
+        assert_eq!(added.extra().keys().collect::<Vec<_>>(), ["extra"]);
+
+        let request = server.last_request("reminders.add");
+        assert_eq!(request.field("user"), Some("U12345678"));
+        assert_eq!(request.field("time"), Some("in 5 minutes"));
+
+        // Defaults fill in what isn't given, and required inputs are still checked
+        let text = String::from("Deploy");
+        let request = RemindersAdd::write_out(HashMap::from([(String::from("text"), &text as &dyn Any)]), Default::default());
+        assert!(matches!(request, Err(SlackError::InvalidInput { reason: InputError::Missing("time"), .. })));
+        let time = String::from("tomorrow");
+        let request = RemindersAdd::write_out(HashMap::from([
+            (String::from("text"), &text as &dyn Any),
+            (String::from("time"), &time as &dyn Any),
+        ]), Default::default()).unwrap();
+        assert_eq!(request.fields["team_id"], "T12345678");
+        assert!(!request.fields.contains_key("user"));
+        assert_eq!(reminder.inputs().len(), 4);
+
+        let info = registry::method("reminders.add").unwrap();
+        assert_eq!(info.rate_limit, Some(RateLimit::Tier2));
+        assert_eq!(info.params.iter().map(|param| (param.name, param.required)).collect::<Vec<_>>(), [
+            ("text", true),
+            ("time", true),
+            ("user", false),
+            ("team_id", false),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_call_raw() {
+        let server = MockServer::start();
+        server.respond("bookmarks.list", MockResponse::ok(json!({ "bookmarks": [{ "id": "Bk1" }] })).with_warning("missing_charset"))
+            .respond("bookmarks.list", MockResponse::error("invalid_auth"));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let response = client.call_raw("bookmarks.list", json!({ "channel_id": "C12345678", "limit": 5 })).await.unwrap();
+        assert_eq!(response.data(), &json!({ "bookmarks": [{ "id": "Bk1" }] }));
+        assert_eq!(response.warnings(), [Warning::MissingCharset]);
+
+        let request = server.last_request("bookmarks.list");
+        assert_eq!(request.token(), Some("xoxb-1234"));
+        request.assert_fields(&[("channel_id", "C12345678"), ("limit", "5")]);
+
+        let result = client.call_raw("bookmarks.list", json!({})).await;
+        assert!(matches!(result, Err(SlackError::ApiError(errors)) if matches!(errors[..], [Error::InvalidAuth])));
+
+        let result = client.call_raw("bookmarks.list", json!(["not", "an", "object"])).await;
+        assert!(matches!(result, Err(SlackError::JsonError(_))));
+        assert_eq!(server.requests_to("bookmarks.list").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields() {
+        let server = MockServer::start();
+        server.respond_ok("users.info", json!({
+            "user": {
+                "id": "U12345678",
+                "name": "tester",
+                "is_bot": false,
+                "who_can_share_contact_card": "EVERYONE",
+                "profile": { "display_name": "Tester", "status_emoji_display_info": [] },
+            },
+            "cache_ts": 1610000000,
+        }));
+        let user = UserId::new("U12345678");
+        let inputs = || HashMap::from([(String::from("user"), &user as &dyn Any)]);
+
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+        let response = client.make_request::<Method!["users.info"]>(inputs()).await.unwrap();
+        assert_eq!(response.data().extra["who_can_share_contact_card"], "EVERYONE");
+        assert_eq!(response.data().profile.as_ref().unwrap().extra["status_emoji_display_info"], json!([]));
+        assert_eq!(response.extra()["cache_ts"], 1610000000);
+
+        let strict = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url())
+            .with_strict_parsing();
+        let result = strict.make_request::<Method!["users.info"]>(inputs()).await;
+        assert!(matches!(
+            result,
+            Err(SlackError::UnknownFields { method: "users.info", fields }) if fields == ["cache_ts", "status_emoji_display_info", "who_can_share_contact_card"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_strict_conversation_kinds() {
+        let server = MockServer::start();
+        server.respond_ok("conversations.list", json!({
+            "channels": [
+                {
+                    "id": "D0C0F7S8Y",
+                    "created": 1498500348,
+                    "is_im": true,
+                    "is_org_shared": false,
+                    "user": "U0BS9U4SV",
+                    "is_user_deleted": false,
+                    "priority": 0,
+                },
+                {
+                    "id": "G0AKFJBEU",
+                    "name": "mpdm-mr.banks--slactions-jackson--beforebot-1",
+                    "is_channel": false,
+                    "is_group": true,
+                    "is_im": false,
+                    "is_mpim": true,
+                    "created": 1493657761,
+                    "creator": "U061F7AUR",
+                    "members": ["U061F7AUR", "U0BS9U4SV"],
+                },
+            ],
+            "response_metadata": { "next_cursor": "" },
+        }));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url())
+            .with_strict_parsing();
+
+        let response = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+        match &response.data().0[..] {
+            [Conversation::Im(im), Conversation::Mpim(mpim)] => {
+                assert_eq!(im.user, UserId::new("U0BS9U4SV"));
+                assert_eq!(im.extra["is_user_deleted"], false);
+                assert_eq!(mpim.id, ChannelId::new("G0AKFJBEU"));
+            }
+            conversations => panic!("Expected an IM and an MPIM, got {:?}", conversations),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let server = MockServer::start();
+        server.respond_numbered_pages("files.list", "files", vec![
+            vec![json!({ "id": "F1" }), json!({ "id": "F2" })],
+            vec![json!({ "id": "F3" })],
+        ]);
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let files = client.make_request_all::<Method!["files.list"]>(HashMap::new()).await.unwrap();
+        let ids = files.data().iter().map(|file| file.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["F1", "F2", "F3"]);
+
+        let requests = server.requests_to("files.list");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].field("page"), None);
+        assert_eq!(requests[1].field("page"), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination() {
+        let server = MockServer::start();
+        server.respond_cursor_pages("conversations.members", "members", vec![
+            vec![json!("U1"), json!("U2")],
+            vec![json!("U3")],
+            vec![json!("U4")],
+        ]);
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let members = client.make_request_all::<Method!["conversations.members"]>(
+            HashMap::from([
+                (String::from("channel"), &ChannelId::new("C12345678") as &dyn Any),
+            ])
+        ).await.unwrap();
+        assert_eq!(members.data(), &["U1", "U2", "U3", "U4"].map(UserId::new));
+
+        let cursors = server.requests_to("conversations.members")
+            .iter()
+            .map(|request| request.field("cursor").map(String::from))
+            .collect::<Vec<_>>();
+        assert_eq!(cursors, [None, Some(String::from("page_2")), Some(String::from("page_3"))]);
+    }
+
+    #[tokio::test]
+    async fn test_proactive_refresh() {
+        let server = MockServer::start();
+        server.respond_ok("oauth.v2.access", refreshed_json())
+            .respond_ok("conversations.list", empty_list());
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let client = rotating_client(&server.api_url(), oauth::unix_now() + 60, refreshed.clone());
+
+        client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "oauth.v2.access");
+        requests[0].assert_fields(&[("grant_type", "refresh_token"), ("refresh_token", "xoxe-1-old")]);
+        assert_eq!(requests[1].token(), Some("xoxb-new"));
+
+        let refreshed = refreshed.lock().unwrap();
+        assert_eq!(refreshed.len(), 1);
+        assert_eq!(refreshed[0].refresh_token, "xoxe-1-new");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_spent_token() {
+        let server = MockServer::start();
+        let mut unusable = refreshed_json();
+        unusable["access_token"] = json!("not-a-token");
+        server.respond_ok("oauth.v2.access", unusable)
+            .respond_ok("oauth.v2.access", refreshed_json())
+            .respond_ok("conversations.list", empty_list());
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let client = rotating_client(&server.api_url(), oauth::unix_now() + 60, refreshed.clone());
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::InvalidToken)));
+        assert_eq!(refreshed.lock().unwrap().len(), 1);
+
+        // Still stale, so this refreshes again, with the refresh token the first one gave
+        client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+
+        let requests = server.requests_to("oauth.v2.access");
+        requests[1].assert_fields(&[("refresh_token", "xoxe-1-new")]);
+        assert_eq!(server.last_request("conversations.list").token(), Some("xoxb-new"));
+        assert_eq!(refreshed.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_on_expired() {
+        let server = MockServer::start();
+        server.respond("conversations.list", MockResponse::error("token_expired"))
+            .respond_ok("conversations.list", empty_list())
+            .respond_ok("oauth.v2.access", refreshed_json());
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let client = rotating_client(&server.api_url(), oauth::unix_now() + 3600, refreshed.clone());
+
+        client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].token(), Some("xoxb-old"));
+        assert_eq!(requests[1].method, "oauth.v2.access");
+        assert_eq!(requests[2].token(), Some("xoxb-new"));
+        assert_eq!(refreshed.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_hosts() {
+        let server = MockServer::start();
+        server.respond("files-pri/T1-F1/notes.txt", MockResponse::raw(200, "Some notes"));
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let mut notes = Vec::new();
+        let written = client.download_url(&format!("{}files-pri/T1-F1/notes.txt", server.api_url()), &mut notes).await.unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(notes, b"Some notes");
+        assert_eq!(server.last_request("files-pri/T1-F1/notes.txt").token(), Some("xoxb-1234"));
+
+        for url in ["https://example.com/files-pri/T1-F1/notes.txt", "http://files.slack.com/files-pri/T1-F1/notes.txt", "not a url"] {
+            let result = client.download_url(url, &mut Vec::new()).await;
+            assert!(matches!(result, Err(SlackError::UntrustedUrl(untrusted)) if untrusted == url));
+        }
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_token_type_check() {
+        let server = MockServer::start();
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_api_url(&server.api_url());
+
+        let result = client.make_request::<Method!["search.messages"]>(
+            HashMap::from([
+                (String::from("query"), &String::from("deploy") as &dyn Any),
+            ])
+        ).await;
+
+        assert!(matches!(
+            result,
+            Err(SlackError::NotAllowedTokenType { method: "search.messages", scope: "search:read" }),
+        ));
+        assert!(server.requests().is_empty());
+
+        // Any one of a method's scopes will do, so user tokens can archive with `channels:write`
+        server.respond_ok("conversations.archive", json!({}));
+        let client = SlackClient::new(Token::parse("xoxp-1234").unwrap())
+            .with_api_url(&server.api_url());
+        client.make_request::<Method!["conversations.archive"]>(
+            HashMap::from([
+                (String::from("channel"), &ChannelId::new("C12345678") as &dyn Any),
+            ])
+        ).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_token_selection() {
+        let server = MockServer::start();
+        server.respond_ok("conversations.list", empty_list())
+            .respond_ok("search.messages", json!({ "query": "deploy", "messages": { "total": 0, "matches": [] } }));
+        let client = SlackClient::new(Token::parse("xoxp-user").unwrap())
+            .with_token(Token::parse("xoxb-bot").unwrap())
+            .with_api_url(&server.api_url());
+
+        let query = String::from("deploy");
+        client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+        client.make_request::<Method!["search.messages"]>(
+            HashMap::from([
+                (String::from("query"), &query as &dyn Any),
+            ])
+        ).await.unwrap();
+        client.make_request_as::<Method!["conversations.list"]>(TokenType::User, HashMap::new()).await.unwrap();
+
+        let tokens = server.requests()
+            .iter()
+            .map(|request| request.token().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, ["xoxb-bot", "xoxp-user", "xoxp-user"]);
+
+        assert!(matches!(
+            client.make_request_as::<Method!["conversations.list"]>(TokenType::App, HashMap::new()).await,
+            Err(SlackError::MissingToken(TokenType::App)),
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scope_preflight() {
+        let server = MockServer::start();
+        server.respond("auth.test", MockResponse::ok(json!({
+            "url": "https://example.slack.com/",
+            "team": "Example",
+            "user": "bot",
+            "team_id": "T1",
+            "user_id": "U1",
+        })).with_scopes("channels:read,groups:read,files:read"));
+        let client = SlackClient::new(Token::parse("xoxb-bot").unwrap())
+            .with_api_url(&server.api_url())
+            .with_scope_preflight();
+
+        // Any one of a method's scopes is enough, so lacking `im:read` and `mpim:read` is fine
+        client.check::<Method!["conversations.list"]>().await.unwrap();
+        assert!(client.granted_scopes(TokenType::Bot).unwrap().contains(&Scope::FILES_READ));
+
+        let result = client.check::<Method!["conversations.create"]>().await;
+        assert!(matches!(
+            result,
+            Err(SlackError::MissingScopes { method: "conversations.create", missing }) if missing == [Scope::CHANNELS_MANAGE, Scope::GROUPS_WRITE],
+        ));
+
+        // Known to be missing scopes, so this shouldn't reach the server
+        let result = client.make_request::<Method!["usergroups.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::MissingScopes { .. })));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "auth.test");
     }
 }
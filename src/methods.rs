@@ -1,29 +1,63 @@
 use std::collections::HashMap;
 use std::any::Any;
 
-use reqwest::RequestBuilder;
-use serde::de::{Deserialize, DeserializeOwned};
+use serde::de::{self, DeserializeOwned};
+use serde::Serialize;
 
+use crate::events::View;
+use crate::registry::{MethodInfo, PaginationStyle, ParamInfo};
 use crate::scopes::Scope;
+use crate::transport::{ApiRequest, Encoding};
 use crate::types::*;
 
-#[macro_export]
-macro_rules! Method {
-    ("admin.analytics.getFile") => { $crate::methods::AdminAnalyticsGetFile };
-    ("admin.apps.approve") => { $crate::methods::AdminAppsApprove };
-    ("conversations.list") => { $crate::methods::ConversationsList };
-}
-
 macro_rules! method_def {
+    (@some) => { None };
+    (@some $value:expr) => { Some($value) };
+    (@or $default:expr) => { $default };
+    (@or $default:expr, $value:expr) => { $value };
+
     ($name:ident =>
         path: $api:literal,
         $(scopes: [$($scopes:literal),*],)?
         $(ratelimit: $limit:ident,)?
-        $(inputs: [$($in_names:literal $(if $in_cond:expr)? => $in_tys:ty),* $(,)?],)?
+        $(encoding: $encoding:ident,)?
+        $(pagination: $pagination:ident,)?
+        $(deprecated: $deprecated:literal,)?
+        $(inputs: [$($in_names:literal => $in_tys:ty),* $(,)?],)?
+        $(required: [$($required:literal),* $(,)?],)?
+        $(any_of: [$([$($any_of:literal),*]),* $(,)?],)?
+        $(one_of: [$([$($one_of:literal),*]),* $(,)?],)?
         $(outputs: [$($ret_names:literal => $ret_tys:ty),* $(,)?],)?
     ) => {
         pub enum $name {}
 
+        // Catch typos in the constraints when the crate is built, rather than when they're checked
+        const _: () = {
+            #[allow(dead_code)]
+            const INPUTS: &[&str] = &[$($($in_names),*)?];
+            $($(assert!(has_input(INPUTS, $required), concat!("Required input `", $required, "` of ", $api, " isn't an input"));)*)?
+            $($($(assert!(has_input(INPUTS, $any_of), concat!("Input `", $any_of, "` of ", $api, " in `any_of` isn't an input"));)*)*)?
+            $($($(assert!(has_input(INPUTS, $one_of), concat!("Input `", $one_of, "` of ", $api, " in `one_of` isn't an input"));)*)*)?
+        };
+
+        inventory::submit! {
+            MethodInfo {
+                name: $api,
+                scopes: &[$($($scopes),*)?],
+                rate_limit: method_def!(@some $(RateLimit::$limit)?),
+                encoding: method_def!(@or Encoding::Form $(, Encoding::$encoding)?),
+                params: {
+                    #[allow(dead_code)]
+                    const REQUIRED: &[&str] = &[$($($required),*)?];
+                    &[$($(ParamInfo { name: $in_names, ty: stringify!($in_tys), required: has_input(REQUIRED, $in_names) }),*)?]
+                },
+                any_of: &[$($(&[$($any_of),*]),*)?],
+                one_of: &[$($(&[$($one_of),*]),*)?],
+                pagination: method_def!(@some $(PaginationStyle::$pagination)?),
+                deprecated: method_def!(@some $($deprecated)?),
+            }
+        }
+
         #[allow(unused_parens)]
         impl Method for $name {
             type Input = ( $( $( $in_tys ),* )? );
@@ -36,7 +70,7 @@ macro_rules! method_def {
 
             $(
             fn required_scopes() -> Vec<Scope> {
-                vec![$( Scope::from_name($scopes) ),*]
+                vec![$( Scope::from_name($scopes).expect(concat!("Unknown scope ", $scopes)) ),*]
             }
             )?
 
@@ -46,54 +80,52 @@ macro_rules! method_def {
             }
             )?
 
-            #[allow(unused_variables, unused_mut)]
-            fn write_out(mut request: RequestBuilder, mut inputs: HashMap<String, &dyn Any>) -> RequestBuilder {
-                let mut json_inputs: HashMap<&'static str, serde_json::Value> = HashMap::new();
+            $(
+            fn encoding() -> Encoding {
+                Encoding::$encoding
+            }
+            )?
 
-                $(
-                $(
+            #[allow(unused_variables)]
+            fn write_out(inputs: HashMap<String, &dyn Any>, unknown: UnknownInputs) -> Result<ApiRequest, SlackError> {
+                let invalid = |reason| SlackError::InvalidInput { method: $api, reason };
+                let mut request = ApiRequest::new($api).with_encoding(Self::encoding());
 
-                let raw_input = inputs
-                    .get($in_names);
-
-                if let Some(input_ref) = raw_input {
-                    json_inputs.insert(
-                        $in_names,
-                        serde_json::to_value(input_ref.downcast_ref::<$in_tys>().unwrap())
-                            .expect(concat!("Couldn't serialize ", $in_names)),
-                    );
-                }
-                // If user supplies a condition, we panic if it doesn't hold
-                $( else if $in_cond && raw_input.is_none() {
-                    panic!(concat!("Expected parameter ", $in_names));
-                } )?
-
-                )*
-                )?
+                $($(write_input::<$in_tys>(&mut request, &inputs, $in_names).map_err(invalid)?;)*)?
 
-                // TODO: Handle unknown parameters
-                // assert!(inputs.is_empty(), "Unhandled inputs to `write_out`: {:?}", inputs.keys());
+                handle_unknown_inputs(&mut request, &inputs, &[$($($in_names),*)?], unknown)
+                    .map_err(invalid)?;
+                check_inputs(
+                    &request,
+                    &[$($($required),*)?],
+                    &[$($(&[$($any_of),*]),*)?],
+                    &[$($(&[$($one_of),*]),*)?],
+                ).map_err(invalid)?;
 
-                request.form(&json_inputs)
+                Ok(request)
             }
 
-            #[allow(unused_variables, unused_mut)]
-            fn parse_data(mut map: HashMap<String, serde_json::Value>) -> Self::Return {
+            #[allow(unused_variables)]
+            fn parse_data(map: &mut HashMap<String, serde_json::Value>) -> Result<Self::Return, SlackError> {
                 let out = ();
 
                 $(
                 let out = ($(
-                    <$ret_tys>::deserialize(map.remove($ret_names).unwrap()).unwrap()
+                    parse_output::<$ret_tys>($api, $ret_names, map.remove($ret_names))?
                 ),*);
                 )?
 
-                debug_assert!(map.is_empty(), "Expected extra data to be empty after parsing, instead got {:?}", map);
-                out
+                Ok(out)
             }
         }
     };
 }
 
+// Methods generated from Slack's spec, and the `Method!` macro naming every method
+mod generated;
+
+pub use generated::*;
+
 pub trait Method {
     type Input;
 
@@ -101,7 +133,8 @@ pub trait Method {
 
     fn api_str() -> &'static str;
 
-    /// The scopes necessary to use this endpoint. Defaults to none
+    /// The scopes that allow using this endpoint, any one of which is enough. Slack lists
+    /// alternatives for different kinds of token and conversation. Defaults to none
     fn required_scopes() -> Vec<Scope> {
         vec![]
     }
@@ -111,9 +144,237 @@ pub trait Method {
         None
     }
 
-    fn write_out(request: RequestBuilder, inputs: HashMap<String, &dyn Any>) -> RequestBuilder;
+    /// How to encode the request body. Defaults to a form, which every method accepts, but
+    /// methods that take structured inputs like `blocks` are better sent as JSON
+    fn encoding() -> Encoding {
+        Encoding::Form
+    }
+
+    /// Describe a call with these inputs, ready to be encoded and sent by any transport, or say
+    /// what's wrong with them. `unknown` says what to do with inputs the method doesn't define
+    fn write_out(inputs: HashMap<String, &dyn Any>, unknown: UnknownInputs) -> Result<ApiRequest, SlackError>;
+
+    /// Take the outputs out of a response's fields, leaving behind any this method doesn't know
+    fn parse_data(map: &mut HashMap<String, serde_json::Value>) -> Result<Self::Return, SlackError>;
+}
+
+/// Methods whose inputs are the fields of the type itself, as defined with
+/// `#[derive(SlackMethod)]`. Call them with [`SlackClient::call`](crate::SlackClient::call)
+pub trait MethodInputs: Method {
+    /// The inputs that are set, in the form [`Method::write_out`] takes them
+    fn inputs(&self) -> HashMap<String, &dyn Any>;
+}
+
+/// What to do with inputs a method doesn't define
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownInputs {
+    /// Fail with [`InputError::Unknown`], since the input is most likely misspelled
+    #[default]
+    Reject,
+    /// Send them as they are, to use parameters Slack has added before the method defines them.
+    /// They need to be given as a `serde_json::Value`, string, boolean or integer
+    Forward,
+}
+
+/// Add the input called `name` to a request if it was given, checking it's the type the method
+/// expects
+#[doc(hidden)]
+pub fn write_input<T: Serialize + 'static>(request: &mut ApiRequest, inputs: &HashMap<String, &dyn Any>, name: &'static str) -> Result<(), InputError> {
+    if let Some(input) = inputs.get(name) {
+        let input = input.downcast_ref::<T>()
+            .ok_or(InputError::WrongType { name, expected: std::any::type_name::<T>() })?;
+        let value = serde_json::to_value(input)
+            .map_err(|_| InputError::Unserializable(name))?;
+
+        request.fields.insert(name.to_string(), value);
+    }
+
+    Ok(())
+}
+
+/// Add `value` as the input called `name`, unless it was given
+#[doc(hidden)]
+pub fn write_default<T: Serialize>(request: &mut ApiRequest, name: &'static str, value: T) -> Result<(), InputError> {
+    if !request.fields.contains_key(name) {
+        let value = serde_json::to_value(value)
+            .map_err(|_| InputError::Unserializable(name))?;
+
+        request.fields.insert(name.to_string(), value);
+    }
+
+    Ok(())
+}
+
+/// Deal with any inputs that aren't in `known` according to `policy`
+#[doc(hidden)]
+pub fn handle_unknown_inputs(request: &mut ApiRequest, inputs: &HashMap<String, &dyn Any>, known: &[&str], policy: UnknownInputs) -> Result<(), InputError> {
+    let mut unknown = inputs.iter()
+        .filter(|(name, _)| !known.contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    // Report the same input every time when there are several
+    unknown.sort_by_key(|(name, _)| name.as_str());
+
+    for (name, input) in unknown {
+        match policy {
+            UnknownInputs::Reject => return Err(InputError::Unknown(name.clone())),
+            UnknownInputs::Forward => {
+                let value = raw_value(*input)
+                    .ok_or_else(|| InputError::NotForwardable(name.clone()))?;
+                request.fields.insert(name.clone(), value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// An input of one of the types that can be forwarded without knowing what the method expects
+fn raw_value(input: &dyn Any) -> Option<serde_json::Value> {
+    if let Some(value) = input.downcast_ref::<serde_json::Value>() {
+        Some(value.clone())
+    } else if let Some(str) = input.downcast_ref::<String>() {
+        Some(str.as_str().into())
+    } else if let Some(str) = input.downcast_ref::<&str>() {
+        Some((*str).into())
+    } else if let Some(bool) = input.downcast_ref::<bool>() {
+        Some((*bool).into())
+    } else if let Some(num) = input.downcast_ref::<u64>() {
+        Some((*num).into())
+    } else {
+        input.downcast_ref::<i64>().map(|num| (*num).into())
+    }
+}
+
+/// Check the inputs given for a call against its method's constraints: every `required` input,
+/// at least one of each `any_of` group, and exactly one of each `one_of` group. Inputs that are
+/// `null` count as missing
+#[doc(hidden)]
+pub fn check_inputs(request: &ApiRequest, required: &[&'static str], any_of: &[&'static [&'static str]], one_of: &[&'static [&'static str]]) -> Result<(), InputError> {
+    let given = |name: &&'static str| request.fields
+        .get(*name)
+        .is_some_and(|value| !value.is_null());
+
+    if let Some(name) = required.iter().find(|name| !given(name)) {
+        return Err(InputError::Missing(name));
+    }
+
+    for group in any_of {
+        if !group.iter().any(given) {
+            return Err(InputError::MissingOneOf(group));
+        }
+    }
+
+    for group in one_of {
+        let present = group.iter().copied().filter(given).collect::<Vec<_>>();
+        match present.len() {
+            0 => return Err(InputError::MissingOneOf(group)),
+            1 => (),
+            _ => return Err(InputError::Conflicting(present)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `name` is one of `inputs`, in a form that can be checked in constants
+const fn has_input(inputs: &[&str], name: &str) -> bool {
+    let mut i = 0;
+    while i < inputs.len() {
+        let (input, name) = (inputs[i].as_bytes(), name.as_bytes());
+        if input.len() == name.len() {
+            let mut j = 0;
+            while j < input.len() && input[j] == name[j] {
+                j += 1;
+            }
+            if j == input.len() {
+                return true;
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Deserialize one of a method's outputs. A missing output is read as `null`, so it's only
+/// accepted if it's declared as an `Option`
+pub(crate) fn parse_output<T: DeserializeOwned>(method: &'static str, field: &'static str, value: Option<serde_json::Value>) -> Result<T, SlackError> {
+    serde_path_to_error::deserialize(value.unwrap_or(serde_json::Value::Null))
+        .map_err(|source| SlackError::Deserialize { method, field, source })
+}
+
+/// Deserialize all of a method's outputs as one struct, taking the fields it has out of the
+/// response and leaving the rest
+#[doc(hidden)]
+pub fn parse_struct<T: DeserializeOwned>(method: &'static str, map: &mut HashMap<String, serde_json::Value>) -> Result<T, SlackError> {
+    let fields = struct_fields::<T>();
+    let outputs = match fields {
+        Some(fields) => fields.iter()
+            .filter_map(|field| Some((field.to_string(), map.remove(*field)?)))
+            .collect(),
+        None => map.drain().collect(),
+    };
+
+    serde_path_to_error::deserialize(serde_json::Value::Object(outputs))
+        .map_err(|source| {
+            // Point at the output that was wrong, as far as it can be named statically
+            let field = source.path().iter().next()
+                .and_then(|segment| match segment {
+                    serde_path_to_error::Segment::Map { key } => fields?.iter().find(|field| *field == key).copied(),
+                    _ => None,
+                })
+                .unwrap_or_else(std::any::type_name::<T>);
+
+            SlackError::Deserialize { method, field, source }
+        })
+}
+
+/// The fields a struct deserializes from, found by asking its `Deserialize` impl. `None` for types
+/// that don't deserialize as a plain struct, like those with flattened fields
+fn struct_fields<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    struct Fields<'a>(&'a mut Option<&'static [&'static str]>);
+
+    impl<'de> de::Deserializer<'de> for Fields<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: de::Visitor<'de>>(self, _: &'static str, fields: &'static [&'static str], _: V) -> Result<V::Value, Self::Error> {
+            *self.0 = Some(fields);
+            Err(de::Error::custom("only after the fields"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+            ignored_any
+        }
+    }
+
+    let mut fields = None;
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
 
-    fn parse_data(map: HashMap<String, serde_json::Value>) -> Self::Return;
+/// How to request the page of results following a response
+#[derive(Debug, PartialEq)]
+pub enum NextPage {
+    /// Pass this value as the `cursor` input
+    Cursor(String),
+    /// Pass this value as the `page` input
+    Page(u64),
+}
+
+/// A method whose results are split over multiple responses, either with cursors or page numbers
+pub trait Paginated: Method {
+    type Item;
+
+    /// Where the next page of results starts, or `None` if this was the last page
+    fn next_page(data: &Self::Return) -> Option<NextPage>;
+
+    /// The results from a single page
+    fn into_items(data: Self::Return) -> Vec<Self::Item>;
 }
 
 method_def! {
@@ -121,7 +382,8 @@ method_def! {
         path: "admin.analytics.getFile",
         scopes: ["admin.analytics:read"],
         ratelimit: Tier2,
-        inputs: ["type" if true => String, "date" => String, "metadata_only" => bool],
+        inputs: ["type" => String, "date" => String, "metadata_only" => bool],
+        required: ["type"],
 }
 
 method_def! {
@@ -138,27 +400,825 @@ method_def! {
         outputs: [],
 }
 
-// TODO
-// method_def! {
-//     ChatPostMessage =>
-//         path: "chat.postMessage",
-//         scopes: ["chat:write", "chat:write:user", "chat:write:bot"],
-//         ratelimit: Tier4,
-//         req_inputs: [channel | "channel" => ChannelId, text | "text" => String, user | "user" => UserId],
-//         inputs: []
-// }
+method_def! {
+    ChatDelete =>
+        path: "chat.delete",
+        scopes: ["chat:write"],
+        ratelimit: Tier3,
+        inputs: [
+            "channel" => ChannelId,
+            "ts" => Timestamp,
+            "as_user" => bool,
+        ],
+        required: ["channel", "ts"],
+        outputs: ["channel" => ChannelId, "ts" => Timestamp],
+}
+
+method_def! {
+    ChatPostMessage =>
+        path: "chat.postMessage",
+        scopes: ["chat:write"],
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: [
+            "channel" => ChannelId,
+            "text" => String,
+            "attachments" => serde_json::Value,
+            "blocks" => serde_json::Value,
+            "icon_emoji" => String,
+            "icon_url" => String,
+            "link_names" => bool,
+            "mrkdwn" => bool,
+            "parse" => String,
+            "reply_broadcast" => bool,
+            "thread_ts" => Timestamp,
+            "unfurl_links" => bool,
+            "unfurl_media" => bool,
+            "username" => String,
+        ],
+        required: ["channel"],
+        any_of: [["text", "blocks", "attachments"]],
+        outputs: ["channel" => ChannelId, "ts" => Timestamp, "message" => Message],
+}
+
+method_def! {
+    ChatUpdate =>
+        path: "chat.update",
+        scopes: ["chat:write"],
+        ratelimit: Tier3,
+        encoding: Json,
+        inputs: [
+            "channel" => ChannelId,
+            "ts" => Timestamp,
+            "text" => String,
+            "attachments" => serde_json::Value,
+            "blocks" => serde_json::Value,
+            "link_names" => bool,
+            "parse" => String,
+            "reply_broadcast" => bool,
+        ],
+        required: ["channel", "ts"],
+        outputs: ["channel" => ChannelId, "ts" => Timestamp, "text" => String, "message" => Message],
+}
+
+method_def! {
+    ConversationsCreate =>
+        path: "conversations.create",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "name" => String,
+            "is_private" => bool,
+            "team_id" => TeamId,
+        ],
+        required: ["name"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsHistory =>
+        path: "conversations.history",
+        scopes: ["channels:history", "groups:history", "im:history", "mpim:history"],
+        ratelimit: Tier3,
+        pagination: Cursor,
+        inputs: [
+            "channel" => ChannelId,
+            "cursor" => String,
+            "inclusive" => bool,
+            "latest" => Timestamp,
+            "limit" => u64,
+            "oldest" => Timestamp,
+        ],
+        required: ["channel"],
+        outputs: ["messages" => Vec<Message>, "has_more" => bool, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for ConversationsHistory {
+    type Item = Message;
+
+    fn next_page((_, _, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((messages, _, _): Self::Return) -> Vec<Message> {
+        messages
+    }
+}
+
+method_def! {
+    ConversationsInfo =>
+        path: "conversations.info",
+        scopes: ["channels:read", "groups:read", "im:read", "mpim:read"],
+        ratelimit: Tier3,
+        inputs: [
+            "channel" => ChannelId,
+            "include_locale" => bool,
+            "include_num_members" => bool,
+        ],
+        required: ["channel"],
+        outputs: ["channel" => Conversation],
+}
 
 method_def! {
     ConversationsList =>
         path: "conversations.list",
         scopes: ["channels:read", "groups:read", "im:read", "mpim:read"],
         ratelimit: Tier2,
+        pagination: Cursor,
         inputs: [
             "cursor" => String,
             "exclude_archived" => bool,
-            "limit "=> u64,
+            "limit" => u64,
             "team_id" => TeamId,
             "types" => String,
         ],
         outputs: ["channels" => Vec<Conversation>, "response_metadata" => ResponseMeta],
 }
+
+impl Paginated for ConversationsList {
+    type Item = Conversation;
+
+    fn next_page((_, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((channels, _): Self::Return) -> Vec<Conversation> {
+        channels
+    }
+}
+
+method_def! {
+    ConversationsMembers =>
+        path: "conversations.members",
+        scopes: ["channels:read", "groups:read", "im:read", "mpim:read"],
+        ratelimit: Tier4,
+        pagination: Cursor,
+        inputs: [
+            "channel" => ChannelId,
+            "cursor" => String,
+            "limit" => u64,
+        ],
+        required: ["channel"],
+        outputs: ["members" => Vec<UserId>, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for ConversationsMembers {
+    type Item = UserId;
+
+    fn next_page((_, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((members, _): Self::Return) -> Vec<UserId> {
+        members
+    }
+}
+
+method_def! {
+    ConversationsReplies =>
+        path: "conversations.replies",
+        scopes: ["channels:history", "groups:history", "im:history", "mpim:history"],
+        ratelimit: Tier3,
+        pagination: Cursor,
+        inputs: [
+            "channel" => ChannelId,
+            "ts" => Timestamp,
+            "cursor" => String,
+            "inclusive" => bool,
+            "latest" => Timestamp,
+            "limit" => u64,
+            "oldest" => Timestamp,
+        ],
+        required: ["channel", "ts"],
+        outputs: ["messages" => Vec<Message>, "has_more" => bool, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for ConversationsReplies {
+    type Item = Message;
+
+    fn next_page((_, _, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((messages, _, _): Self::Return) -> Vec<Message> {
+        messages
+    }
+}
+
+method_def! {
+    FilesDelete =>
+        path: "files.delete",
+        scopes: ["files:write"],
+        ratelimit: Tier3,
+        inputs: ["file" => FileId],
+        required: ["file"],
+        outputs: [],
+}
+
+method_def! {
+    FilesInfo =>
+        path: "files.info",
+        scopes: ["files:read"],
+        ratelimit: Tier4,
+        inputs: [
+            "file" => FileId,
+            "count" => u64,
+            "cursor" => String,
+            "limit" => u64,
+            "page" => u64,
+        ],
+        required: ["file"],
+        outputs: ["file" => File, "comments" => Option<Vec<Comment>>, "response_metadata" => Option<ResponseMeta>],
+}
+
+method_def! {
+    FilesList =>
+        path: "files.list",
+        scopes: ["files:read"],
+        ratelimit: Tier3,
+        pagination: Page,
+        inputs: [
+            "channel" => ChannelId,
+            "count" => u64,
+            "page" => u64,
+            "show_files_hidden_by_limit" => bool,
+            "team_id" => TeamId,
+            "ts_from" => Timestamp,
+            "ts_to" => Timestamp,
+            "types" => String,
+            "user" => UserId,
+        ],
+        outputs: ["files" => Vec<File>, "paging" => Paging],
+}
+
+impl Paginated for FilesList {
+    type Item = File;
+
+    fn next_page((_, paging): &Self::Return) -> Option<NextPage> {
+        paging.next_page().map(NextPage::Page)
+    }
+
+    fn into_items((files, _): Self::Return) -> Vec<File> {
+        files
+    }
+}
+
+method_def! {
+    FilesRemoteAdd =>
+        path: "files.remote.add",
+        scopes: ["remote_files:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "external_id" => String,
+            "external_url" => String,
+            "title" => String,
+            "filetype" => String,
+            "indexable_file_contents" => String,
+        ],
+        required: ["external_id", "external_url", "title"],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    FilesRemoteInfo =>
+        path: "files.remote.info",
+        scopes: ["remote_files:read"],
+        ratelimit: Tier2,
+        inputs: ["external_id" => String, "file" => FileId],
+        one_of: [["external_id", "file"]],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    FilesRemoteList =>
+        path: "files.remote.list",
+        scopes: ["remote_files:read"],
+        ratelimit: Tier2,
+        pagination: Cursor,
+        inputs: [
+            "channel" => ChannelId,
+            "cursor" => String,
+            "limit" => u64,
+            "ts_from" => Timestamp,
+            "ts_to" => Timestamp,
+        ],
+        outputs: ["files" => Vec<File>, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for FilesRemoteList {
+    type Item = File;
+
+    fn next_page((_, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((files, _): Self::Return) -> Vec<File> {
+        files
+    }
+}
+
+method_def! {
+    FilesRemoteRemove =>
+        path: "files.remote.remove",
+        scopes: ["remote_files:write"],
+        ratelimit: Tier2,
+        inputs: ["external_id" => String, "file" => FileId],
+        one_of: [["external_id", "file"]],
+        outputs: [],
+}
+
+method_def! {
+    FilesRemoteShare =>
+        path: "files.remote.share",
+        scopes: ["remote_files:share"],
+        ratelimit: Tier2,
+        inputs: ["channels" => String, "external_id" => String, "file" => FileId],
+        required: ["channels"],
+        one_of: [["external_id", "file"]],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    FilesRemoteUpdate =>
+        path: "files.remote.update",
+        scopes: ["remote_files:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "external_id" => String,
+            "external_url" => String,
+            "file" => FileId,
+            "filetype" => String,
+            "indexable_file_contents" => String,
+            "title" => String,
+        ],
+        one_of: [["external_id", "file"]],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    FilesRevokePublicURL =>
+        path: "files.revokePublicURL",
+        scopes: ["files:write"],
+        ratelimit: Tier3,
+        inputs: ["file" => FileId],
+        required: ["file"],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    FilesSharedPublicURL =>
+        path: "files.sharedPublicURL",
+        scopes: ["files:write"],
+        ratelimit: Tier3,
+        inputs: ["file" => FileId],
+        required: ["file"],
+        outputs: ["file" => File],
+}
+
+method_def! {
+    ReactionsAdd =>
+        path: "reactions.add",
+        scopes: ["reactions:write"],
+        ratelimit: Tier3,
+        inputs: [
+            "channel" => ChannelId,
+            "name" => String,
+            "timestamp" => Timestamp,
+        ],
+        required: ["channel", "name", "timestamp"],
+        outputs: [],
+}
+
+method_def! {
+    ReactionsGet =>
+        path: "reactions.get",
+        scopes: ["reactions:read"],
+        ratelimit: Tier3,
+        inputs: [
+            "channel" => ChannelId,
+            "timestamp" => Timestamp,
+            "file" => FileId,
+            "file_comment" => String,
+            "full" => bool,
+        ],
+        any_of: [["channel", "file", "file_comment"]],
+        outputs: [
+            "type" => String,
+            "channel" => Option<ChannelId>,
+            "message" => Option<Message>,
+            "file" => Option<File>,
+            "comment" => Option<Comment>,
+        ],
+}
+
+method_def! {
+    ReactionsRemove =>
+        path: "reactions.remove",
+        scopes: ["reactions:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "channel" => ChannelId,
+            "name" => String,
+            "timestamp" => Timestamp,
+        ],
+        required: ["channel", "name", "timestamp"],
+        outputs: [],
+}
+
+method_def! {
+    SearchAll =>
+        path: "search.all",
+        scopes: ["search:read"],
+        ratelimit: Tier2,
+        pagination: Page,
+        inputs: [
+            "query" => String,
+            "count" => u64,
+            "highlight" => bool,
+            "page" => u64,
+            "sort" => String,
+            "sort_dir" => String,
+            "team_id" => TeamId,
+        ],
+        required: ["query"],
+        outputs: [
+            "query" => String,
+            "messages" => SearchResults<MessageMatch>,
+            "files" => SearchResults<File>,
+            "posts" => SearchResults<serde_json::Value>,
+        ],
+}
+
+impl Paginated for SearchAll {
+    type Item = SearchMatch;
+
+    // Messages and files are paged together, so keep going until both have run out
+    fn next_page((_, messages, files, _): &Self::Return) -> Option<NextPage> {
+        messages.next_page()
+            .max(files.next_page())
+            .map(NextPage::Page)
+    }
+
+    fn into_items((_, messages, files, _): Self::Return) -> Vec<SearchMatch> {
+        messages.matches
+            .into_iter()
+            .map(SearchMatch::Message)
+            .chain(files.matches.into_iter().map(SearchMatch::File))
+            .collect()
+    }
+}
+
+method_def! {
+    SearchFiles =>
+        path: "search.files",
+        scopes: ["search:read"],
+        ratelimit: Tier2,
+        pagination: Page,
+        inputs: [
+            "query" => String,
+            "count" => u64,
+            "highlight" => bool,
+            "page" => u64,
+            "sort" => String,
+            "sort_dir" => String,
+            "team_id" => TeamId,
+        ],
+        required: ["query"],
+        outputs: ["query" => String, "files" => SearchResults<File>],
+}
+
+impl Paginated for SearchFiles {
+    type Item = File;
+
+    fn next_page((_, files): &Self::Return) -> Option<NextPage> {
+        files.next_page().map(NextPage::Page)
+    }
+
+    fn into_items((_, files): Self::Return) -> Vec<File> {
+        files.matches
+    }
+}
+
+method_def! {
+    SearchMessages =>
+        path: "search.messages",
+        scopes: ["search:read"],
+        ratelimit: Tier2,
+        pagination: Page,
+        inputs: [
+            "query" => String,
+            "count" => u64,
+            "highlight" => bool,
+            "page" => u64,
+            "sort" => String,
+            "sort_dir" => String,
+            "team_id" => TeamId,
+        ],
+        required: ["query"],
+        outputs: ["query" => String, "messages" => SearchResults<MessageMatch>],
+}
+
+impl Paginated for SearchMessages {
+    type Item = MessageMatch;
+
+    fn next_page((_, messages): &Self::Return) -> Option<NextPage> {
+        messages.next_page().map(NextPage::Page)
+    }
+
+    fn into_items((_, messages): Self::Return) -> Vec<MessageMatch> {
+        messages.matches
+    }
+}
+
+method_def! {
+    UsergroupsCreate =>
+        path: "usergroups.create",
+        scopes: ["usergroups:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "name" => String,
+            "channels" => String,
+            "description" => String,
+            "handle" => String,
+            "include_count" => bool,
+            "team_id" => TeamId,
+        ],
+        required: ["name"],
+        outputs: ["usergroup" => Usergroup],
+}
+
+method_def! {
+    UsergroupsDisable =>
+        path: "usergroups.disable",
+        scopes: ["usergroups:write"],
+        ratelimit: Tier2,
+        inputs: ["usergroup" => UsergroupId, "include_count" => bool, "team_id" => TeamId],
+        required: ["usergroup"],
+        outputs: ["usergroup" => Usergroup],
+}
+
+method_def! {
+    UsergroupsEnable =>
+        path: "usergroups.enable",
+        scopes: ["usergroups:write"],
+        ratelimit: Tier2,
+        inputs: ["usergroup" => UsergroupId, "include_count" => bool, "team_id" => TeamId],
+        required: ["usergroup"],
+        outputs: ["usergroup" => Usergroup],
+}
+
+method_def! {
+    UsergroupsList =>
+        path: "usergroups.list",
+        scopes: ["usergroups:read"],
+        ratelimit: Tier2,
+        inputs: [
+            "include_count" => bool,
+            "include_disabled" => bool,
+            "include_users" => bool,
+            "team_id" => TeamId,
+        ],
+        outputs: ["usergroups" => Vec<Usergroup>],
+}
+
+method_def! {
+    UsergroupsUpdate =>
+        path: "usergroups.update",
+        scopes: ["usergroups:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "usergroup" => UsergroupId,
+            "channels" => String,
+            "description" => String,
+            "handle" => String,
+            "include_count" => bool,
+            "name" => String,
+            "team_id" => TeamId,
+        ],
+        required: ["usergroup"],
+        outputs: ["usergroup" => Usergroup],
+}
+
+method_def! {
+    UsergroupsUsersList =>
+        path: "usergroups.users.list",
+        scopes: ["usergroups:read"],
+        ratelimit: Tier2,
+        inputs: ["usergroup" => UsergroupId, "include_disabled" => bool, "team_id" => TeamId],
+        required: ["usergroup"],
+        outputs: ["users" => Vec<UserId>],
+}
+
+method_def! {
+    UsergroupsUsersUpdate =>
+        path: "usergroups.users.update",
+        scopes: ["usergroups:write"],
+        ratelimit: Tier2,
+        inputs: [
+            "usergroup" => UsergroupId,
+            "users" => String,
+            "include_count" => bool,
+            "team_id" => TeamId,
+        ],
+        required: ["usergroup", "users"],
+        outputs: ["usergroup" => Usergroup],
+}
+
+method_def! {
+    UsersInfo =>
+        path: "users.info",
+        scopes: ["users:read"],
+        ratelimit: Tier4,
+        inputs: ["user" => UserId, "include_locale" => bool],
+        required: ["user"],
+        outputs: ["user" => User],
+}
+
+method_def! {
+    UsersList =>
+        path: "users.list",
+        scopes: ["users:read"],
+        ratelimit: Tier2,
+        pagination: Cursor,
+        inputs: [
+            "cursor" => String,
+            "include_locale" => bool,
+            "limit" => u64,
+            "team_id" => TeamId,
+        ],
+        outputs: ["members" => Vec<User>, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for UsersList {
+    type Item = User;
+
+    fn next_page((_, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((members, _): Self::Return) -> Vec<User> {
+        members
+    }
+}
+
+method_def! {
+    ViewsOpen =>
+        path: "views.open",
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: ["view" => serde_json::Value, "trigger_id" => String, "interactivity_pointer" => String],
+        required: ["view"],
+        one_of: [["trigger_id", "interactivity_pointer"]],
+        outputs: ["view" => View],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_paging() {
+        let data = SearchMessages::parse_data(&mut serde_json::from_value(serde_json::json!({
+            "query": "deploy",
+            "messages": {
+                "total": 3,
+                "paging": { "count": 2, "total": 3, "page": 1, "pages": 2 },
+                "matches": [{
+                    "iid": "a1",
+                    "channel": { "id": "C0123", "name": "general" },
+                    "user": "U0123",
+                    "text": "deploy done",
+                    "ts": "1610000000.000200",
+                    "type": "message",
+                    "previous": { "user": "U0456", "text": "deploying now", "ts": "1610000000.000100" },
+                }],
+            },
+        })).unwrap()).unwrap();
+
+        assert_eq!(SearchMessages::next_page(&data), Some(NextPage::Page(2)));
+
+        let matches = SearchMessages::into_items(data);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].previous.as_ref().unwrap().text, "deploying now");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut fields = serde_json::from_value(serde_json::json!({
+            "user": { "id": "U0123", "name": "tester", "is_bot": false, "profile": { "status_expiration": "soon" } },
+        })).unwrap();
+        let err = UsersInfo::parse_data(&mut fields).unwrap_err();
+        assert!(matches!(
+            &err,
+            SlackError::Deserialize { method: "users.info", field: "user", source } if source.path().to_string() == "profile.status_expiration"
+        ));
+
+        let err = UsersInfo::parse_data(&mut HashMap::new()).unwrap_err();
+        assert!(matches!(err, SlackError::Deserialize { method: "users.info", field: "user", .. }));
+
+        // Optional outputs can be left out
+        let mut fields = serde_json::from_value(serde_json::json!({ "file": { "id": "F0123" } })).unwrap();
+        let (file, comments, meta) = FilesInfo::parse_data(&mut fields).unwrap();
+        assert_eq!(file.id.as_str(), "F0123");
+        assert!(comments.is_none() && meta.is_none());
+    }
+
+    #[test]
+    fn test_reactions_get_items() {
+        let mut fields = serde_json::from_value(serde_json::json!({
+            "type": "message",
+            "channel": "C0123",
+            "message": { "type": "message", "text": "Lunch?", "ts": "1610000000.000100" },
+        })).unwrap();
+        let (kind, channel, message, file, comment) = ReactionsGet::parse_data(&mut fields).unwrap();
+        assert_eq!(kind, "message");
+        assert_eq!(channel.unwrap().as_str(), "C0123");
+        assert_eq!(message.unwrap().text, "Lunch?");
+        assert!(file.is_none() && comment.is_none());
+
+        let mut fields = serde_json::from_value(serde_json::json!({
+            "type": "file",
+            "file": { "id": "F0123", "reactions": [{ "name": "tada", "count": 1, "users": ["U0123"] }] },
+        })).unwrap();
+        let (kind, channel, message, file, comment) = ReactionsGet::parse_data(&mut fields).unwrap();
+        assert_eq!(kind, "file");
+        assert_eq!(file.unwrap().id.as_str(), "F0123");
+        assert!(channel.is_none() && message.is_none() && comment.is_none());
+
+        let mut fields = serde_json::from_value(serde_json::json!({
+            "type": "file_comment",
+            "file": { "id": "F0123" },
+            "comment": { "id": "Fc0123", "comment": "Nice", "reactions": [] },
+        })).unwrap();
+        let (kind, _, message, file, comment) = ReactionsGet::parse_data(&mut fields).unwrap();
+        assert_eq!(kind, "file_comment");
+        assert_eq!(file.unwrap().id.as_str(), "F0123");
+        assert_eq!(comment.unwrap().extra["comment"], "Nice");
+        assert!(message.is_none());
+    }
+
+    #[test]
+    fn test_input_constraints() {
+        fn reason<T: Method>(inputs: HashMap<String, &dyn Any>) -> Option<InputError> {
+            match T::write_out(inputs, UnknownInputs::Reject) {
+                Ok(_) => None,
+                Err(SlackError::InvalidInput { method, reason }) if method == T::api_str() => Some(reason),
+                Err(err) => panic!("Unexpected error {:?}", err),
+            }
+        }
+
+        let channel = ChannelId::new("C0123");
+        let text = String::from("Hello");
+        let file = FileId::new("F0123");
+        let blocks = serde_json::json!([{ "type": "divider" }]);
+
+        assert_eq!(reason::<ChatPostMessage>(HashMap::from([
+            (String::from("text"), &text as &dyn Any),
+        ])), Some(InputError::Missing("channel")));
+        assert_eq!(reason::<ChatPostMessage>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+        ])), Some(InputError::MissingOneOf(&["text", "blocks", "attachments"])));
+        assert_eq!(reason::<ChatPostMessage>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("blocks"), &blocks as &dyn Any),
+        ])), None);
+        assert_eq!(reason::<ChatPostMessage>(HashMap::from([
+            (String::from("channel"), &text as &dyn Any),
+            (String::from("text"), &text as &dyn Any),
+        ])), Some(InputError::WrongType { name: "channel", expected: std::any::type_name::<ChannelId>() }));
+
+        assert_eq!(reason::<FilesRemoteInfo>(HashMap::new()), Some(InputError::MissingOneOf(&["external_id", "file"])));
+        assert_eq!(reason::<FilesRemoteInfo>(HashMap::from([
+            (String::from("external_id"), &text as &dyn Any),
+            (String::from("file"), &file as &dyn Any),
+        ])), Some(InputError::Conflicting(vec!["external_id", "file"])));
+    }
+
+    #[test]
+    fn test_unknown_inputs() {
+        let limit = 100u64;
+        let include_new = serde_json::json!(true);
+        let channel = ChannelId::new("C0123");
+
+        let request = ConversationsList::write_out(HashMap::from([
+            (String::from("limit"), &limit as &dyn Any),
+        ]), UnknownInputs::Reject).unwrap();
+        assert_eq!(request.fields["limit"], 100);
+
+        let err = ConversationsList::write_out(HashMap::from([
+            (String::from("limt"), &limit as &dyn Any),
+        ]), UnknownInputs::Reject).unwrap_err();
+        assert!(matches!(err, SlackError::InvalidInput { reason: InputError::Unknown(name), .. } if name == "limt"));
+
+        let request = ConversationsList::write_out(HashMap::from([
+            (String::from("limit"), &limit as &dyn Any),
+            (String::from("include_new"), &include_new as &dyn Any),
+        ]), UnknownInputs::Forward).unwrap();
+        assert_eq!(request.fields["include_new"], true);
+
+        let err = ConversationsList::write_out(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+        ]), UnknownInputs::Forward).unwrap_err();
+        assert!(matches!(err, SlackError::InvalidInput { reason: InputError::NotForwardable(name), .. } if name == "channel"));
+    }
+}
@@ -0,0 +1,368 @@
+// Generated by `cargo xtask codegen` from spec/slack_web.json, so don't edit it by hand. To tune
+// a method, move its definition into methods.rs and generate this again
+
+use super::*;
+
+method_def! {
+    AuthTest =>
+        path: "auth.test",
+        ratelimit: TierSpecial,
+        outputs: [
+            "bot_id" => Option<BotId>,
+            "is_enterprise_install" => Option<bool>,
+            "team" => String,
+            "team_id" => TeamId,
+            "url" => String,
+            "user" => String,
+            "user_id" => UserId,
+        ],
+}
+
+method_def! {
+    ChatGetPermalink =>
+        path: "chat.getPermalink",
+        ratelimit: TierSpecial,
+        inputs: ["channel" => ChannelId, "message_ts" => Timestamp],
+        required: ["channel", "message_ts"],
+        outputs: ["channel" => ChannelId, "permalink" => String],
+}
+
+method_def! {
+    ChatPostEphemeral =>
+        path: "chat.postEphemeral",
+        scopes: ["chat:write"],
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: [
+            "channel" => ChannelId,
+            "user" => UserId,
+            "text" => String,
+            "blocks" => serde_json::Value,
+            "attachments" => serde_json::Value,
+            "thread_ts" => Timestamp,
+            "icon_emoji" => String,
+            "icon_url" => String,
+            "link_names" => bool,
+            "parse" => String,
+            "username" => String,
+        ],
+        required: ["channel", "user"],
+        any_of: [["text", "blocks", "attachments"]],
+        outputs: ["message_ts" => Timestamp],
+}
+
+method_def! {
+    ChatScheduleMessage =>
+        path: "chat.scheduleMessage",
+        scopes: ["chat:write"],
+        ratelimit: Tier3,
+        encoding: Json,
+        inputs: [
+            "channel" => ChannelId,
+            "post_at" => u64,
+            "text" => String,
+            "blocks" => serde_json::Value,
+            "attachments" => serde_json::Value,
+            "thread_ts" => Timestamp,
+            "reply_broadcast" => bool,
+            "unfurl_links" => bool,
+            "unfurl_media" => bool,
+        ],
+        required: ["channel", "post_at"],
+        outputs: [
+            "channel" => ChannelId,
+            "message" => Message,
+            "post_at" => u64,
+            "scheduled_message_id" => String,
+        ],
+}
+
+method_def! {
+    ConversationsArchive =>
+        path: "conversations.archive",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId],
+        required: ["channel"],
+        outputs: [],
+}
+
+method_def! {
+    ConversationsInvite =>
+        path: "conversations.invite",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier3,
+        inputs: ["channel" => ChannelId, "users" => String, "force" => bool],
+        required: ["channel", "users"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsJoin =>
+        path: "conversations.join",
+        scopes: ["channels:join"],
+        ratelimit: Tier3,
+        inputs: ["channel" => ChannelId],
+        required: ["channel"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsKick =>
+        path: "conversations.kick",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier3,
+        inputs: ["channel" => ChannelId, "user" => UserId],
+        required: ["channel", "user"],
+        outputs: [],
+}
+
+method_def! {
+    ConversationsLeave =>
+        path: "conversations.leave",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier3,
+        inputs: ["channel" => ChannelId],
+        required: ["channel"],
+        outputs: ["not_in_channel" => Option<bool>],
+}
+
+method_def! {
+    ConversationsRename =>
+        path: "conversations.rename",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId, "name" => String],
+        required: ["channel", "name"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsSetPurpose =>
+        path: "conversations.setPurpose",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId, "purpose" => String],
+        required: ["channel", "purpose"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsSetTopic =>
+        path: "conversations.setTopic",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId, "topic" => String],
+        required: ["channel", "topic"],
+        outputs: ["channel" => Conversation],
+}
+
+method_def! {
+    ConversationsUnarchive =>
+        path: "conversations.unarchive",
+        scopes: ["channels:manage", "channels:write", "groups:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId],
+        required: ["channel"],
+        outputs: [],
+}
+
+method_def! {
+    PinsAdd =>
+        path: "pins.add",
+        scopes: ["pins:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId, "timestamp" => Timestamp],
+        required: ["channel", "timestamp"],
+        outputs: [],
+}
+
+method_def! {
+    PinsList =>
+        path: "pins.list",
+        scopes: ["pins:read"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId],
+        required: ["channel"],
+        outputs: ["items" => Vec<serde_json::Value>],
+}
+
+method_def! {
+    PinsRemove =>
+        path: "pins.remove",
+        scopes: ["pins:write"],
+        ratelimit: Tier2,
+        inputs: ["channel" => ChannelId, "timestamp" => Timestamp],
+        required: ["channel", "timestamp"],
+        outputs: [],
+}
+
+method_def! {
+    UsersConversations =>
+        path: "users.conversations",
+        scopes: ["channels:read", "groups:read", "im:read", "mpim:read"],
+        ratelimit: Tier3,
+        pagination: Cursor,
+        inputs: [
+            "cursor" => String,
+            "exclude_archived" => bool,
+            "limit" => u64,
+            "team_id" => TeamId,
+            "types" => String,
+            "user" => UserId,
+        ],
+        outputs: ["channels" => Vec<Conversation>, "response_metadata" => ResponseMeta],
+}
+
+impl Paginated for UsersConversations {
+    type Item = Conversation;
+
+    fn next_page((_, meta): &Self::Return) -> Option<NextPage> {
+        meta.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))
+    }
+
+    fn into_items((channels, _): Self::Return) -> Vec<Conversation> {
+        channels
+    }
+}
+
+method_def! {
+    UsersGetPresence =>
+        path: "users.getPresence",
+        scopes: ["users:read"],
+        ratelimit: Tier3,
+        inputs: ["user" => UserId],
+        outputs: [
+            "auto_away" => Option<bool>,
+            "connection_count" => Option<u64>,
+            "last_activity" => Option<u64>,
+            "manual_away" => Option<bool>,
+            "online" => Option<bool>,
+            "presence" => String,
+        ],
+}
+
+method_def! {
+    UsersLookupByEmail =>
+        path: "users.lookupByEmail",
+        scopes: ["users:read.email"],
+        ratelimit: Tier3,
+        inputs: ["email" => String],
+        required: ["email"],
+        outputs: ["user" => User],
+}
+
+method_def! {
+    UsersSetActive =>
+        path: "users.setActive",
+        scopes: ["users:write"],
+        ratelimit: Tier2,
+        deprecated: "Slack no longer tracks activity this way, so this does nothing",
+        outputs: [],
+}
+
+method_def! {
+    ViewsPublish =>
+        path: "views.publish",
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: ["user_id" => UserId, "view" => serde_json::Value, "hash" => String],
+        required: ["user_id", "view"],
+        outputs: ["view" => View],
+}
+
+method_def! {
+    ViewsPush =>
+        path: "views.push",
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: [
+            "trigger_id" => String,
+            "interactivity_pointer" => String,
+            "view" => serde_json::Value,
+        ],
+        required: ["view"],
+        one_of: [["trigger_id", "interactivity_pointer"]],
+        outputs: ["view" => View],
+}
+
+method_def! {
+    ViewsUpdate =>
+        path: "views.update",
+        ratelimit: Tier4,
+        encoding: Json,
+        inputs: [
+            "view" => serde_json::Value,
+            "external_id" => String,
+            "view_id" => String,
+            "hash" => String,
+        ],
+        required: ["view"],
+        one_of: [["view_id", "external_id"]],
+        outputs: ["view" => View],
+}
+
+#[macro_export]
+macro_rules! Method {
+    ("admin.analytics.getFile") => { $crate::methods::AdminAnalyticsGetFile };
+    ("admin.apps.approve") => { $crate::methods::AdminAppsApprove };
+    ("auth.test") => { $crate::methods::AuthTest };
+    ("chat.delete") => { $crate::methods::ChatDelete };
+    ("chat.getPermalink") => { $crate::methods::ChatGetPermalink };
+    ("chat.postEphemeral") => { $crate::methods::ChatPostEphemeral };
+    ("chat.postMessage") => { $crate::methods::ChatPostMessage };
+    ("chat.scheduleMessage") => { $crate::methods::ChatScheduleMessage };
+    ("chat.update") => { $crate::methods::ChatUpdate };
+    ("conversations.archive") => { $crate::methods::ConversationsArchive };
+    ("conversations.create") => { $crate::methods::ConversationsCreate };
+    ("conversations.history") => { $crate::methods::ConversationsHistory };
+    ("conversations.info") => { $crate::methods::ConversationsInfo };
+    ("conversations.invite") => { $crate::methods::ConversationsInvite };
+    ("conversations.join") => { $crate::methods::ConversationsJoin };
+    ("conversations.kick") => { $crate::methods::ConversationsKick };
+    ("conversations.leave") => { $crate::methods::ConversationsLeave };
+    ("conversations.list") => { $crate::methods::ConversationsList };
+    ("conversations.members") => { $crate::methods::ConversationsMembers };
+    ("conversations.rename") => { $crate::methods::ConversationsRename };
+    ("conversations.replies") => { $crate::methods::ConversationsReplies };
+    ("conversations.setPurpose") => { $crate::methods::ConversationsSetPurpose };
+    ("conversations.setTopic") => { $crate::methods::ConversationsSetTopic };
+    ("conversations.unarchive") => { $crate::methods::ConversationsUnarchive };
+    ("files.delete") => { $crate::methods::FilesDelete };
+    ("files.info") => { $crate::methods::FilesInfo };
+    ("files.list") => { $crate::methods::FilesList };
+    ("files.remote.add") => { $crate::methods::FilesRemoteAdd };
+    ("files.remote.info") => { $crate::methods::FilesRemoteInfo };
+    ("files.remote.list") => { $crate::methods::FilesRemoteList };
+    ("files.remote.remove") => { $crate::methods::FilesRemoteRemove };
+    ("files.remote.share") => { $crate::methods::FilesRemoteShare };
+    ("files.remote.update") => { $crate::methods::FilesRemoteUpdate };
+    ("files.revokePublicURL") => { $crate::methods::FilesRevokePublicURL };
+    ("files.sharedPublicURL") => { $crate::methods::FilesSharedPublicURL };
+    ("pins.add") => { $crate::methods::PinsAdd };
+    ("pins.list") => { $crate::methods::PinsList };
+    ("pins.remove") => { $crate::methods::PinsRemove };
+    ("reactions.add") => { $crate::methods::ReactionsAdd };
+    ("reactions.get") => { $crate::methods::ReactionsGet };
+    ("reactions.remove") => { $crate::methods::ReactionsRemove };
+    ("search.all") => { $crate::methods::SearchAll };
+    ("search.files") => { $crate::methods::SearchFiles };
+    ("search.messages") => { $crate::methods::SearchMessages };
+    ("usergroups.create") => { $crate::methods::UsergroupsCreate };
+    ("usergroups.disable") => { $crate::methods::UsergroupsDisable };
+    ("usergroups.enable") => { $crate::methods::UsergroupsEnable };
+    ("usergroups.list") => { $crate::methods::UsergroupsList };
+    ("usergroups.update") => { $crate::methods::UsergroupsUpdate };
+    ("usergroups.users.list") => { $crate::methods::UsergroupsUsersList };
+    ("usergroups.users.update") => { $crate::methods::UsergroupsUsersUpdate };
+    ("users.conversations") => { $crate::methods::UsersConversations };
+    ("users.getPresence") => { $crate::methods::UsersGetPresence };
+    ("users.info") => { $crate::methods::UsersInfo };
+    ("users.list") => { $crate::methods::UsersList };
+    ("users.lookupByEmail") => { $crate::methods::UsersLookupByEmail };
+    ("users.setActive") => { $crate::methods::UsersSetActive };
+    ("views.open") => { $crate::methods::ViewsOpen };
+    ("views.publish") => { $crate::methods::ViewsPublish };
+    ("views.push") => { $crate::methods::ViewsPush };
+    ("views.update") => { $crate::methods::ViewsUpdate };
+}
@@ -0,0 +1,337 @@
+//! A local stand-in for the Slack API, for testing code that uses [`SlackClient`](crate::SlackClient)
+//! without a real workspace. Point a client at [`MockServer::api_url`], queue up the responses
+//! each method should give, then check the requests that were made. For tests that need Slack to
+//! remember what happened, [`FakeWorkspace`] holds users, channels and messages in memory, and
+//! [`PayloadKit`] builds signed events and interactions for testing handlers.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+mod payloads;
+mod workspace;
+pub use payloads::{PayloadKit, SignedRequest};
+pub use workspace::{FakeMessage, FakeUser, FakeWorkspace};
+
+/// A request the mock server received
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// The API method, such as `conversations.list`
+    pub method: String,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub fields: HashMap<String, String>,
+}
+
+impl RecordedRequest {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+
+    /// The bearer token the request was authenticated with
+    pub fn token(&self) -> Option<&str> {
+        self.headers
+            .get("authorization")
+            .and_then(|auth| auth.strip_prefix("Bearer "))
+    }
+
+    /// Panic unless every one of `fields` was sent with the given value
+    pub fn assert_fields(&self, fields: &[(&str, &str)]) {
+        for (name, value) in fields {
+            assert_eq!(self.field(name), Some(*value), "Field `{}` of `{}` request", name, self.method);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Json(Value),
+    Raw(String),
+}
+
+/// A canned response for the mock server to give
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: MockBody,
+}
+
+impl MockResponse {
+    /// A successful response. `data` must be an object, and has `"ok": true` added to it
+    pub fn ok(data: Value) -> MockResponse {
+        let mut object = match data {
+            Value::Object(object) => object,
+            Value::Null => serde_json::Map::new(),
+            other => panic!("Mock response data must be an object, got {}", other),
+        };
+        object.insert(String::from("ok"), Value::Bool(true));
+
+        MockResponse::status(200, MockBody::Json(Value::Object(object)))
+    }
+
+    /// A failed response with the given error code, such as `channel_not_found`
+    pub fn error(error: &str) -> MockResponse {
+        MockResponse::status(200, MockBody::Json(json!({ "ok": false, "error": error })))
+    }
+
+    /// An HTTP 429, asking the client to wait `retry_after` seconds
+    pub fn rate_limited(retry_after: u64) -> MockResponse {
+        MockResponse::status(429, MockBody::Json(json!({ "ok": false, "error": "ratelimited" })))
+            .with_header("Retry-After", &retry_after.to_string())
+    }
+
+    /// Any body at all, for simulating responses that aren't JSON
+    pub fn raw(status: u16, body: &str) -> MockResponse {
+        MockResponse::status(status, MockBody::Raw(body.to_string()))
+    }
+
+    fn status(status: u16, body: MockBody) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![(String::from("Content-Type"), String::from("application/json; charset=utf-8"))],
+            body,
+        }
+    }
+
+    /// Add a warning, as Slack does for deprecated methods or missing charsets
+    pub fn with_warning(mut self, warning: &str) -> MockResponse {
+        if let MockBody::Json(Value::Object(object)) = &mut self.body {
+            let warnings = match object.get("warning") {
+                Some(Value::String(existing)) => format!("{},{}", existing, warning),
+                _ => warning.to_string(),
+            };
+            object.insert(String::from("warning"), Value::String(warnings));
+        }
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Report the token as having these scopes, in the `x-oauth-scopes` header
+    pub fn with_scopes(self, scopes: &str) -> MockResponse {
+        self.with_header("x-oauth-scopes", scopes)
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let body = match self.body {
+            MockBody::Json(value) => value.to_string(),
+            MockBody::Raw(raw) => raw,
+        };
+
+        let mut builder = Response::builder().status(self.status);
+        for (name, value) in self.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::from(body)).expect("Mock responses should be valid")
+    }
+}
+
+pub(crate) type Handler = Arc<dyn Fn(&RecordedRequest) -> MockResponse + Send + Sync>;
+
+/// A running local HTTP server, answering every request under `/api/` with `handler`
+pub(crate) struct LocalServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl LocalServer {
+    pub(crate) fn start(handler: Handler) -> LocalServer {
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle(handler.clone(), request)))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(make_service);
+        let addr = server.local_addr();
+
+        let (shutdown, on_shutdown) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            on_shutdown.await.ok();
+        }));
+
+        LocalServer { addr, shutdown: Some(shutdown) }
+    }
+
+    pub(crate) fn api_url(&self) -> String {
+        format!("http://{}/api/", self.addr)
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+async fn handle(handler: Handler, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+
+    let method = match parts.uri.path().strip_prefix("/api/") {
+        Some(method) => method.to_string(),
+        None => return Ok(MockResponse::raw(404, "Not Found").into_response()),
+    };
+
+    let headers: HashMap<String, String> = parts.headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let content_type = headers.get("content-type").cloned().unwrap_or_default();
+
+    let recorded = RecordedRequest {
+        method,
+        fields: parse_fields(&content_type, &body),
+        headers,
+    };
+
+    Ok(handler(&recorded).into_response())
+}
+
+/// Parse the fields out of a request body, whether it's form or JSON encoded
+fn parse_fields(content_type: &str, body: &[u8]) -> HashMap<String, String> {
+    if content_type.starts_with("application/json") {
+        let object: serde_json::Map<String, Value> = serde_json::from_slice(body).unwrap_or_default();
+        object.into_iter()
+            .map(|(name, value)| match value {
+                Value::String(str) => (name, str),
+                other => (name, other.to_string()),
+            })
+            .collect()
+    } else {
+        form_urlencoded::parse(body)
+            .into_owned()
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: HashMap<String, VecDeque<MockResponse>>,
+    requests: Vec<RecordedRequest>,
+}
+
+impl MockState {
+    /// Responses are given in the order they were queued, with the last one repeating forever
+    fn respond(&mut self, request: &RecordedRequest) -> MockResponse {
+        self.requests.push(request.clone());
+
+        match self.responses.get_mut(&request.method) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().unwrap(),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => MockResponse::error("unknown_method"),
+        }
+    }
+}
+
+/// A mock Slack API serving canned responses per method, and recording every request it gets.
+/// Methods without any queued responses fail with `unknown_method`
+pub struct MockServer {
+    server: LocalServer,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockServer {
+    /// Start a server on a free local port. Must be called within a Tokio runtime
+    pub fn start() -> MockServer {
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let handler_state = state.clone();
+        let server = LocalServer::start(Arc::new(move |request| {
+            handler_state.lock().unwrap().respond(request)
+        }));
+
+        MockServer { server, state }
+    }
+
+    /// The base URL to give [`SlackClient::with_api_url`](crate::SlackClient::with_api_url)
+    pub fn api_url(&self) -> String {
+        self.server.api_url()
+    }
+
+    /// Queue a response for a method
+    pub fn respond(&self, method: &str, response: MockResponse) -> &MockServer {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Queue a successful response for a method
+    pub fn respond_ok(&self, method: &str, data: Value) -> &MockServer {
+        self.respond(method, MockResponse::ok(data))
+    }
+
+    /// Queue one response per page for a cursor-paginated method, with the items of each page
+    /// under `key`
+    pub fn respond_cursor_pages(&self, method: &str, key: &str, pages: Vec<Vec<Value>>) -> &MockServer {
+        let count = pages.len();
+        for (index, items) in pages.into_iter().enumerate() {
+            let next_cursor = if index + 1 < count {
+                format!("page_{}", index + 2)
+            } else {
+                String::new()
+            };
+
+            self.respond_ok(method, json!({
+                key: items,
+                "response_metadata": { "next_cursor": next_cursor },
+            }));
+        }
+        self
+    }
+
+    /// Queue one response per page for a method with numbered pages, with the items of each page
+    /// under `key` and the `paging` object alongside them
+    pub fn respond_numbered_pages(&self, method: &str, key: &str, pages: Vec<Vec<Value>>) -> &MockServer {
+        let count = pages.len() as u64;
+        let total = pages.iter().map(Vec::len).sum::<usize>();
+        let per_page = pages.first().map_or(0, Vec::len);
+
+        for (index, items) in pages.into_iter().enumerate() {
+            self.respond_ok(method, json!({
+                key: items,
+                "paging": { "count": per_page, "total": total, "page": index + 1, "pages": count },
+            }));
+        }
+        self
+    }
+
+    /// Every request received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// The requests received for a single method
+    pub fn requests_to(&self, method: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.method == method)
+            .collect()
+    }
+
+    /// The most recent request for a method, panicking if there wasn't one
+    pub fn last_request(&self, method: &str) -> RecordedRequest {
+        self.requests_to(method)
+            .pop()
+            .unwrap_or_else(|| panic!("No requests were made to `{}`", method))
+    }
+}
@@ -0,0 +1,300 @@
+//! Building the requests Slack sends to an app, signed just as Slack signs them, for testing
+//! handlers without a workspace. Feed them to a [`Dispatcher`](crate::events::Dispatcher), or to
+//! whatever HTTP endpoint wraps one.
+
+use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use serde_json::{json, Value};
+
+use crate::oauth::unix_now;
+use crate::signing::{Verifier, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::types::{AppId, ChannelId, TeamId, UserId};
+
+const FORM: &str = "application/x-www-form-urlencoded";
+const JSON: &str = "application/json";
+
+/// A signed request, ready to be handed to an endpoint
+#[derive(Debug, Clone)]
+pub struct SignedRequest {
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+/// Builds realistic payloads for one app in one workspace
+pub struct PayloadKit {
+    verifier: Verifier,
+    team_id: TeamId,
+    app_id: AppId,
+    timestamp: Option<u64>,
+}
+
+impl PayloadKit {
+    pub fn new(signing_secret: &str) -> PayloadKit {
+        PayloadKit {
+            verifier: Verifier::new(signing_secret),
+            team_id: TeamId::new("T00000001"),
+            app_id: AppId::new("A00000001"),
+            timestamp: None,
+        }
+    }
+
+    pub fn with_team(mut self, team_id: &TeamId) -> PayloadKit {
+        self.team_id = team_id.clone();
+        self
+    }
+
+    pub fn with_app(mut self, app_id: &AppId) -> PayloadKit {
+        self.app_id = app_id.clone();
+        self
+    }
+
+    /// Sign requests as if they were sent at `timestamp`, rather than now, to test rejecting
+    /// stale requests
+    pub fn signed_at(mut self, timestamp: u64) -> PayloadKit {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sign any body
+    pub fn sign(&self, content_type: &str, body: Vec<u8>) -> SignedRequest {
+        let timestamp = self.timestamp.unwrap_or_else(unix_now);
+        let signature = self.verifier.sign(timestamp, &body);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).expect("Content types are valid headers"));
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(&signature).expect("Signatures are valid headers"));
+
+        SignedRequest { headers, body }
+    }
+
+    /// Sign a JSON body as is, such as an event callback from a
+    /// [`FakeWorkspace`](super::FakeWorkspace) subscription
+    pub fn sign_json(&self, body: &Value) -> SignedRequest {
+        self.sign(JSON, body.to_string().into_bytes())
+    }
+
+    fn sign_form(&self, fields: &[(&str, &str)]) -> SignedRequest {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+
+        self.sign(FORM, body.into_bytes())
+    }
+
+    /// The challenge Slack sends when an Events API request URL is saved
+    pub fn url_verification(&self, challenge: &str) -> SignedRequest {
+        self.sign_json(&json!({
+            "token": "Jhj5dZrVaK7ZwHHjRyZWjbDl",
+            "challenge": challenge,
+            "type": "url_verification",
+        }))
+    }
+
+    /// An event callback wrapping `event`, which needs at least a `type`
+    pub fn event(&self, event: Value) -> SignedRequest {
+        let event_time = self.timestamp.unwrap_or_else(unix_now);
+
+        self.sign_json(&json!({
+            "token": "Jhj5dZrVaK7ZwHHjRyZWjbDl",
+            "team_id": self.team_id,
+            "api_app_id": self.app_id,
+            "event": event,
+            "type": "event_callback",
+            "event_id": format!("Ev{:08}", event_time % 100_000_000),
+            "event_time": event_time,
+            "authorizations": [{
+                "enterprise_id": null,
+                "team_id": self.team_id,
+                "user_id": "U00000001",
+                "is_bot": true,
+                "is_enterprise_install": false,
+            }],
+            "is_ext_shared_channel": false,
+        }))
+    }
+
+    /// A `message` event for a message posted in a channel
+    pub fn message(&self, channel: &ChannelId, user: &UserId, text: &str) -> SignedRequest {
+        let ts = format!("{}.000100", self.timestamp.unwrap_or_else(unix_now));
+
+        self.event(json!({
+            "type": "message",
+            "channel": channel,
+            "user": user,
+            "text": text,
+            "ts": ts,
+            "event_ts": ts,
+            "channel_type": "channel",
+        }))
+    }
+
+    /// An `app_mention` event for a message mentioning the app's bot
+    pub fn app_mention(&self, channel: &ChannelId, user: &UserId, text: &str) -> SignedRequest {
+        let ts = format!("{}.000100", self.timestamp.unwrap_or_else(unix_now));
+
+        self.event(json!({
+            "type": "app_mention",
+            "channel": channel,
+            "user": user,
+            "text": text,
+            "ts": ts,
+            "event_ts": ts,
+        }))
+    }
+
+    /// A slash command, such as `/deploy`, invoked in a channel
+    pub fn slash_command(&self, command: &str, text: &str, channel: &ChannelId, user: &UserId) -> SignedRequest {
+        let response_url = format!("https://hooks.slack.com/commands/{}/1234567890/abcdefghijklmnop", self.team_id);
+
+        self.sign_form(&[
+            ("token", "gIkuvaNzQIHg97ATvDxqgjtO"),
+            ("team_id", self.team_id.as_str()),
+            ("team_domain", "example"),
+            ("channel_id", channel.as_str()),
+            ("channel_name", "general"),
+            ("user_id", user.as_str()),
+            ("user_name", "tester"),
+            ("command", command),
+            ("text", text),
+            ("api_app_id", self.app_id.as_str()),
+            ("response_url", &response_url),
+            ("trigger_id", "13345224609.738474920.8088930838d88f008e0"),
+        ])
+    }
+
+    /// A user clicking a button with `action_id` and `value` on a message in a channel
+    pub fn block_action(&self, action_id: &str, value: &str, channel: &ChannelId, user: &UserId) -> SignedRequest {
+        self.interaction(json!({
+            "type": "block_actions",
+            "user": { "id": user, "username": "tester", "name": "tester", "team_id": self.team_id },
+            "api_app_id": self.app_id,
+            "token": "9s8d9as89d8as9d8as989",
+            "container": { "type": "message", "channel_id": channel, "is_ephemeral": false },
+            "trigger_id": "13345224609.738474920.8088930838d88f008e0",
+            "team": { "id": self.team_id, "domain": "example" },
+            "channel": { "id": channel, "name": "general" },
+            "response_url": "https://hooks.slack.com/actions/T00000001/1234567890/abcdefghijklmnop",
+            "actions": [{
+                "action_id": action_id,
+                "block_id": "actions",
+                "type": "button",
+                "value": value,
+                "action_ts": format!("{}.000200", self.timestamp.unwrap_or_else(unix_now)),
+            }],
+        }))
+    }
+
+    /// A user submitting a modal with `callback_id`. `values` holds the input values, keyed by
+    /// block ID then action ID, as in the view's state
+    pub fn view_submission(&self, callback_id: &str, values: Value, user: &UserId) -> SignedRequest {
+        self.interaction(json!({
+            "type": "view_submission",
+            "team": { "id": self.team_id, "domain": "example" },
+            "user": { "id": user, "username": "tester", "name": "tester", "team_id": self.team_id },
+            "api_app_id": self.app_id,
+            "token": "9s8d9as89d8as9d8as989",
+            "trigger_id": "13345224609.738474920.8088930838d88f008e0",
+            "view": {
+                "id": "V00000001",
+                "type": "modal",
+                "callback_id": callback_id,
+                "private_metadata": "",
+                "hash": "156772938.1827394",
+                "state": { "values": values },
+            },
+        }))
+    }
+
+    /// Interactions are sent as JSON in a `payload` form field
+    fn interaction(&self, payload: Value) -> SignedRequest {
+        self.sign_form(&[("payload", &payload.to_string())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::events::{DispatchError, Dispatcher, Event, Payload};
+    use crate::mock::FakeWorkspace;
+    use crate::signing::VerifyError;
+
+    const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+
+    fn dispatch(dispatcher: &Dispatcher, request: &SignedRequest) -> Result<Option<Value>, DispatchError> {
+        dispatcher.dispatch(&request.headers, &request.body)
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (events, actions) = (seen.clone(), seen.clone());
+
+        let dispatcher = Dispatcher::new(Verifier::new(SECRET))
+            .on_event("message", move |callback| {
+                if let Event::Message(message) = &callback.event {
+                    events.lock().unwrap().push(message.text.clone().unwrap());
+                }
+            })
+            .on_command("/deploy", |command| Some(json!({ "text": format!("Deploying {}", command.text) })))
+            .on_action("approve", move |_, action| actions.lock().unwrap().push(action.value.clone().unwrap()))
+            .on_view("feedback", |submission| {
+                let rating = &submission.view.state.as_ref().unwrap().values["rating"]["rating_input"]["value"];
+                (rating == "0").then(|| json!({ "response_action": "errors", "errors": { "rating": "Too low" } }))
+            });
+
+        let kit = PayloadKit::new(SECRET);
+        let (channel, user) = (ChannelId::new("C00000001"), UserId::new("U00000002"));
+
+        assert_eq!(dispatch(&dispatcher, &kit.url_verification("3eZbrw1aB")).unwrap(), Some(json!({ "challenge": "3eZbrw1aB" })));
+        assert_eq!(dispatch(&dispatcher, &kit.message(&channel, &user, "hello")).unwrap(), None);
+        assert_eq!(dispatch(&dispatcher, &kit.app_mention(&channel, &user, "<@U00000001> hi")).unwrap(), None);
+        assert_eq!(
+            dispatch(&dispatcher, &kit.slash_command("/deploy", "api v2", &channel, &user)).unwrap(),
+            Some(json!({ "text": "Deploying api v2" })),
+        );
+        assert_eq!(dispatch(&dispatcher, &kit.block_action("approve", "release-42", &channel, &user)).unwrap(), None);
+
+        let values = |rating: &str| json!({ "rating": { "rating_input": { "type": "plain_text_input", "value": rating } } });
+        assert_eq!(dispatch(&dispatcher, &kit.view_submission("feedback", values("5"), &user)).unwrap(), None);
+        assert!(dispatch(&dispatcher, &kit.view_submission("feedback", values("0"), &user)).unwrap().is_some());
+
+        assert_eq!(*seen.lock().unwrap(), ["hello", "release-42"]);
+    }
+
+    #[test]
+    fn test_rejected_requests() {
+        let dispatcher = Dispatcher::new(Verifier::new(SECRET));
+        let (channel, user) = (ChannelId::new("C00000001"), UserId::new("U00000002"));
+
+        let forged = PayloadKit::new("not the secret").message(&channel, &user, "hello");
+        assert!(matches!(dispatch(&dispatcher, &forged), Err(DispatchError::Unverified(VerifyError::InvalidSignature))));
+
+        let stale = PayloadKit::new(SECRET).signed_at(unix_now() - 3600).message(&channel, &user, "hello");
+        assert!(matches!(dispatch(&dispatcher, &stale), Err(DispatchError::Unverified(VerifyError::Expired))));
+
+        let mut tampered = PayloadKit::new(SECRET).message(&channel, &user, "hello");
+        tampered.body.extend_from_slice(b" ");
+        assert!(matches!(dispatch(&dispatcher, &tampered), Err(DispatchError::Unverified(VerifyError::InvalidSignature))));
+
+        let garbled = PayloadKit::new(SECRET).sign(JSON, b"{not json".to_vec());
+        assert!(matches!(dispatch(&dispatcher, &garbled), Err(DispatchError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_events() {
+        let workspace = FakeWorkspace::start();
+        let mut events = workspace.subscribe();
+        let alice = workspace.add_user("alice");
+        workspace.post(workspace.general(), &alice.id, "Good morning");
+
+        let kit = PayloadKit::new(SECRET);
+        let request = kit.sign_json(&events.recv().await.unwrap());
+        let payload = Payload::parse(JSON, &request.body).unwrap();
+
+        assert!(matches!(
+            payload,
+            Payload::EventCallback(callback) if matches!(&callback.event, Event::Message(message) if message.user.as_ref() == Some(&alice.id))
+        ));
+    }
+}
@@ -0,0 +1,955 @@
+//! A fake Slack workspace held in memory, for integration tests that need Slack to remember
+//! things between calls. Posting a message makes it show up in `conversations.history`, replies
+//! thread under their parent, reactions stick, and the usual errors come back for channels and
+//! messages that don't exist.
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use super::{LocalServer, MockResponse, RecordedRequest};
+use crate::SlackClient;
+use crate::oauth::unix_now;
+use crate::types::{ChannelId, Reaction, TeamId, Timestamp, Token, UserId};
+
+/// How many items a page holds when the request doesn't give a `limit`
+const DEFAULT_LIMIT: usize = 100;
+
+/// A member of a fake workspace, along with the token that acts as them
+#[derive(Debug, Clone)]
+pub struct FakeUser {
+    pub id: UserId,
+    pub name: String,
+    pub is_bot: bool,
+    pub token: Token,
+}
+
+/// A message as a fake workspace stores it
+#[derive(Debug, Clone)]
+pub struct FakeMessage {
+    pub channel: ChannelId,
+    pub user: UserId,
+    pub text: String,
+    pub ts: Timestamp,
+    /// The timestamp of the thread's parent, if this is a reply
+    pub thread_ts: Option<Timestamp>,
+    /// Whether this reply was also sent to the channel
+    pub reply_broadcast: bool,
+    pub reactions: Vec<Reaction>,
+}
+
+impl FakeMessage {
+    /// Whether this message shows up in the channel's history, rather than only in a thread
+    fn is_top_level(&self) -> bool {
+        self.thread_ts.is_none() || self.reply_broadcast
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FakeChannel {
+    id: ChannelId,
+    name: String,
+    created: u64,
+    creator: UserId,
+    is_private: bool,
+    is_archived: bool,
+    members: Vec<UserId>,
+}
+
+impl FakeChannel {
+    fn visible_to(&self, user: &UserId) -> bool {
+        !self.is_private || self.members.contains(user)
+    }
+}
+
+/// Order timestamps by their seconds then their sequence number, as Slack does
+fn ts_key(ts: &str) -> (u64, u64) {
+    let (secs, seq) = ts.split_once('.').unwrap_or((ts, "0"));
+    (secs.parse().unwrap_or(0), seq.parse().unwrap_or(0))
+}
+
+type Reply = Result<Value, &'static str>;
+
+struct WorkspaceState {
+    team_id: TeamId,
+    created: u64,
+    next_id: u64,
+    next_seq: u64,
+    users: Vec<FakeUser>,
+    channels: Vec<FakeChannel>,
+    messages: Vec<FakeMessage>,
+    requests: Vec<RecordedRequest>,
+    subscribers: Vec<mpsc::UnboundedSender<Value>>,
+}
+
+impl WorkspaceState {
+    fn new() -> WorkspaceState {
+        WorkspaceState {
+            team_id: TeamId::new("T00000001"),
+            created: unix_now(),
+            next_id: 1,
+            next_seq: 1,
+            users: Vec::new(),
+            channels: Vec::new(),
+            messages: Vec::new(),
+            requests: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    fn next_id(&mut self, prefix: char) -> String {
+        let id = format!("{}{:08}", prefix, self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Every timestamp handed out is unique and later than the last
+    fn next_ts(&mut self) -> Timestamp {
+        let ts = Timestamp::new(&format!("{}.{:06}", self.created, self.next_seq));
+        self.next_seq += 1;
+        ts
+    }
+
+    fn add_user(&mut self, name: &str, is_bot: bool) -> FakeUser {
+        let id = UserId::new(&self.next_id('U'));
+        let token = if is_bot {
+            format!("xoxb-fake-{}", id)
+        } else {
+            format!("xoxp-fake-{}", id)
+        };
+
+        let user = FakeUser {
+            id,
+            name: name.to_string(),
+            is_bot,
+            token: Token::parse(&token).expect("Fake tokens should parse"),
+        };
+        self.users.push(user.clone());
+        user
+    }
+
+    fn add_channel(&mut self, name: &str, creator: &UserId, is_private: bool) -> ChannelId {
+        let channel = FakeChannel {
+            id: ChannelId::new(&self.next_id('C')),
+            name: name.to_string(),
+            created: unix_now(),
+            creator: creator.clone(),
+            is_private,
+            is_archived: false,
+            members: vec![creator.clone()],
+        };
+
+        if !is_private {
+            self.emit(json!({
+                "type": "channel_created",
+                "channel": {
+                    "id": channel.id,
+                    "name": channel.name,
+                    "created": channel.created,
+                    "creator": channel.creator,
+                },
+            }));
+        }
+
+        let id = channel.id.clone();
+        self.channels.push(channel);
+        id
+    }
+
+    fn channel_mut(&mut self, id: &ChannelId) -> &mut FakeChannel {
+        self.channels
+            .iter_mut()
+            .find(|channel| &channel.id == id)
+            .unwrap_or_else(|| panic!("No channel `{}` in the fake workspace", id))
+    }
+
+    /// Wrap an event the way the Events API delivers it, and send it to every subscriber
+    fn emit(&mut self, event: Value) {
+        let envelope = json!({
+            "type": "event_callback",
+            "team_id": self.team_id,
+            "api_app_id": "A00000001",
+            "event_id": self.next_id('E'),
+            "event_time": unix_now(),
+            "event": event,
+        });
+
+        self.subscribers.retain(|subscriber| subscriber.send(envelope.clone()).is_ok());
+    }
+
+    fn post(&mut self, channel: &ChannelId, user: &UserId, text: &str, thread_ts: Option<Timestamp>, reply_broadcast: bool) -> FakeMessage {
+        let message = FakeMessage {
+            channel: channel.clone(),
+            user: user.clone(),
+            text: text.to_string(),
+            ts: self.next_ts(),
+            thread_ts,
+            reply_broadcast,
+            reactions: Vec::new(),
+        };
+        self.messages.push(message.clone());
+
+        let mut event = json!({
+            "type": "message",
+            "channel": message.channel,
+            "channel_type": self.channel_type(channel),
+            "user": message.user,
+            "text": message.text,
+            "ts": message.ts,
+            "event_ts": message.ts,
+        });
+        if let Some(thread_ts) = &message.thread_ts {
+            event["thread_ts"] = json!(thread_ts);
+        }
+        if message.reply_broadcast {
+            event["subtype"] = json!("thread_broadcast");
+        }
+        self.emit(event);
+
+        message
+    }
+
+    fn channel_type(&self, id: &ChannelId) -> &'static str {
+        match self.channels.iter().find(|channel| &channel.id == id) {
+            Some(channel) if channel.is_private => "group",
+            _ => "channel",
+        }
+    }
+
+    fn handle(&mut self, request: &RecordedRequest) -> MockResponse {
+        self.requests.push(request.clone());
+
+        let user = match request.token() {
+            None => return MockResponse::error("not_authed"),
+            Some(token) => match self.users.iter().find(|user| user.token.as_str() == token) {
+                Some(user) => user.id.clone(),
+                None => return MockResponse::error("invalid_auth"),
+            },
+        };
+
+        let reply = match request.method.as_str() {
+            "auth.test" => self.auth_test(&user),
+            "chat.delete" => self.chat_delete(&user, request),
+            "chat.postMessage" => self.chat_post_message(&user, request),
+            "chat.update" => self.chat_update(&user, request),
+            "conversations.create" => self.conversations_create(&user, request),
+            "conversations.history" => self.conversations_history(&user, request),
+            "conversations.info" => self.conversations_info(&user, request),
+            "conversations.list" => self.conversations_list(&user, request),
+            "conversations.members" => self.conversations_members(&user, request),
+            "conversations.replies" => self.conversations_replies(&user, request),
+            "reactions.add" => self.reactions_add(&user, request),
+            "reactions.get" => self.reactions_get(&user, request),
+            "reactions.remove" => self.reactions_remove(&user, request),
+            "users.info" => self.users_info(request),
+            "users.list" => self.users_list(request),
+            _ => Err("unknown_method"),
+        };
+
+        match reply {
+            Ok(data) => MockResponse::ok(data),
+            Err(error) => MockResponse::error(error),
+        }
+    }
+
+    fn auth_test(&self, user: &UserId) -> Reply {
+        let name = self.users.iter().find(|member| &member.id == user).map(|member| member.name.as_str());
+
+        Ok(json!({
+            "url": "https://fake.slack.com/",
+            "team": "Fake Workspace",
+            "user": name,
+            "team_id": self.team_id,
+            "user_id": user,
+            "is_enterprise_install": false,
+        }))
+    }
+
+    /// Look up a channel by ID, or by name as `chat.postMessage` allows, hiding private channels
+    /// from non-members just as Slack does
+    fn visible_channel(&self, user: &UserId, channel: &str) -> Result<&FakeChannel, &'static str> {
+        let name = channel.strip_prefix('#').unwrap_or(channel);
+
+        self.channels
+            .iter()
+            .find(|found| found.id.as_str() == channel || found.name == name)
+            .filter(|found| found.visible_to(user))
+            .ok_or("channel_not_found")
+    }
+
+    fn message_index(&self, channel: &ChannelId, ts: &str) -> Result<usize, &'static str> {
+        self.messages
+            .iter()
+            .position(|message| &message.channel == channel && message.ts.as_str() == ts)
+            .ok_or("message_not_found")
+    }
+
+    fn conversation_json(&self, channel: &FakeChannel, viewer: &UserId) -> Value {
+        json!({
+            "id": channel.id,
+            "name": channel.name,
+            "name_normalized": channel.name,
+            "created": channel.created,
+            "creator": channel.creator,
+            "is_archived": channel.is_archived,
+            "is_channel": !channel.is_private,
+            "is_group": channel.is_private,
+            "is_im": false,
+            "is_mpim": false,
+            "is_private": channel.is_private,
+            "is_general": channel.name == "general",
+            "is_shared": false,
+            "is_ext_shared": false,
+            "is_org_shared": false,
+            "is_pending_ext_shared": false,
+            "pending_shared": [],
+            "is_member": channel.members.contains(viewer),
+            "num_members": channel.members.len(),
+            "unlinked": 0,
+            "parent_conversation": null,
+            "previous_names": [],
+            "topic": { "value": "", "creator": "", "last_set": 0 },
+            "purpose": { "value": "", "creator": "", "last_set": 0 },
+        })
+    }
+
+    fn message_json(&self, message: &FakeMessage) -> Value {
+        let mut json = json!({
+            "type": "message",
+            "user": message.user,
+            "text": message.text,
+            "ts": message.ts,
+            "team": self.team_id,
+        });
+
+        if let Some(thread_ts) = &message.thread_ts {
+            json["thread_ts"] = json!(thread_ts);
+            if message.reply_broadcast {
+                json["subtype"] = json!("thread_broadcast");
+            }
+        }
+
+        let replies = self.replies(&message.channel, &message.ts);
+        if let Some(latest) = replies.last() {
+            let mut reply_users = Vec::new();
+            for reply in &replies {
+                if !reply_users.contains(&reply.user) {
+                    reply_users.push(reply.user.clone());
+                }
+            }
+
+            json["thread_ts"] = json!(message.ts);
+            json["reply_count"] = json!(replies.len());
+            json["reply_users_count"] = json!(reply_users.len());
+            json["reply_users"] = json!(reply_users);
+            json["latest_reply"] = json!(latest.ts);
+        }
+
+        if !message.reactions.is_empty() {
+            json["reactions"] = json!(message.reactions);
+        }
+
+        json
+    }
+
+    fn replies(&self, channel: &ChannelId, thread_ts: &Timestamp) -> Vec<&FakeMessage> {
+        self.messages
+            .iter()
+            .filter(|message| &message.channel == channel && message.thread_ts.as_ref() == Some(thread_ts))
+            .collect()
+    }
+
+    fn user_json(&self, user: &FakeUser) -> Value {
+        json!({
+            "id": user.id,
+            "team_id": self.team_id,
+            "name": user.name,
+            "deleted": false,
+            "real_name": user.name,
+            "is_admin": false,
+            "is_owner": false,
+            "is_bot": user.is_bot,
+            "is_app_user": false,
+            "updated": self.created,
+            "profile": {
+                "real_name": user.name,
+                "display_name": user.name,
+                "team": self.team_id,
+            },
+        })
+    }
+
+    fn chat_post_message(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?;
+        if channel.is_archived {
+            return Err("is_archived");
+        }
+        let channel = channel.id.clone();
+
+        let text = request.field("text").unwrap_or_default();
+        if text.is_empty() && request.field("blocks").is_none() && request.field("attachments").is_none() {
+            return Err("no_text");
+        }
+
+        // Replying to a reply puts the message in the same thread
+        let thread_ts = match request.field("thread_ts") {
+            Some(ts) => {
+                let parent = &self.messages[self.message_index(&channel, ts).map_err(|_| "thread_not_found")?];
+                Some(parent.thread_ts.clone().unwrap_or_else(|| parent.ts.clone()))
+            }
+            None => None,
+        };
+        let reply_broadcast = thread_ts.is_some() && flag(request, "reply_broadcast");
+
+        let message = self.post(&channel, user, text, thread_ts, reply_broadcast);
+
+        Ok(json!({
+            "channel": channel,
+            "ts": message.ts,
+            "message": self.message_json(&message),
+        }))
+    }
+
+    fn chat_update(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?.id.clone();
+        let index = self.message_index(&channel, required(request, "ts")?)?;
+        if &self.messages[index].user != user {
+            return Err("cant_update_message");
+        }
+
+        let previous = self.message_json(&self.messages[index]);
+        if let Some(text) = request.field("text") {
+            self.messages[index].text = text.to_string();
+        }
+        let message = self.messages[index].clone();
+        let json = self.message_json(&message);
+
+        let event_ts = self.next_ts();
+        self.emit(json!({
+            "type": "message",
+            "subtype": "message_changed",
+            "hidden": true,
+            "channel": channel,
+            "channel_type": self.channel_type(&channel),
+            "message": json,
+            "previous_message": previous,
+            "ts": event_ts,
+            "event_ts": event_ts,
+        }));
+
+        Ok(json!({
+            "channel": channel,
+            "ts": message.ts,
+            "text": message.text,
+            "message": json,
+        }))
+    }
+
+    fn chat_delete(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?.id.clone();
+        let index = self.message_index(&channel, required(request, "ts")?)?;
+        if &self.messages[index].user != user {
+            return Err("cant_delete_message");
+        }
+
+        let previous = self.message_json(&self.messages[index]);
+        let message = self.messages.remove(index);
+
+        let event_ts = self.next_ts();
+        self.emit(json!({
+            "type": "message",
+            "subtype": "message_deleted",
+            "hidden": true,
+            "channel": channel,
+            "channel_type": self.channel_type(&channel),
+            "deleted_ts": message.ts,
+            "previous_message": previous,
+            "ts": event_ts,
+            "event_ts": event_ts,
+        }));
+
+        Ok(json!({ "channel": channel, "ts": message.ts }))
+    }
+
+    fn conversations_create(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let name = required(request, "name")?;
+        let valid = name.len() <= 80 && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            return Err("invalid_name_specials");
+        }
+        if self.channels.iter().any(|channel| channel.name == name) {
+            return Err("name_taken");
+        }
+
+        let id = self.add_channel(name, user, flag(request, "is_private"));
+        let channel = self.visible_channel(user, id.as_str())?;
+
+        Ok(json!({ "channel": self.conversation_json(channel, user) }))
+    }
+
+    fn conversations_info(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?;
+
+        Ok(json!({ "channel": self.conversation_json(channel, user) }))
+    }
+
+    fn conversations_list(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let types = request.field("types").unwrap_or("public_channel");
+        let exclude_archived = flag(request, "exclude_archived");
+
+        let channels = self.channels
+            .iter()
+            .filter(|channel| channel.visible_to(user))
+            .filter(|channel| !(exclude_archived && channel.is_archived))
+            .filter(|channel| {
+                let kind = if channel.is_private { "private_channel" } else { "public_channel" };
+                types.split(',').any(|wanted| wanted.trim() == kind)
+            })
+            .map(|channel| self.conversation_json(channel, user))
+            .collect();
+
+        let (channels, next_cursor) = page(channels, request)?;
+        Ok(json!({ "channels": channels, "response_metadata": { "next_cursor": next_cursor } }))
+    }
+
+    fn conversations_members(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?;
+        let members = channel.members.iter().map(|member| json!(member)).collect();
+
+        let (members, next_cursor) = page(members, request)?;
+        Ok(json!({ "members": members, "response_metadata": { "next_cursor": next_cursor } }))
+    }
+
+    fn conversations_history(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = &self.visible_channel(user, required(request, "channel")?)?.id;
+
+        let mut messages = self.messages
+            .iter()
+            .filter(|message| &message.channel == channel && message.is_top_level())
+            .filter(|message| in_range(request, &message.ts))
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|message| std::cmp::Reverse(ts_key(message.ts.as_str())));
+
+        let messages = messages.into_iter().map(|message| self.message_json(message)).collect();
+        let (messages, next_cursor) = page(messages, request)?;
+        Ok(json!({
+            "messages": messages,
+            "has_more": !next_cursor.is_empty(),
+            "response_metadata": { "next_cursor": next_cursor },
+        }))
+    }
+
+    fn conversations_replies(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = &self.visible_channel(user, required(request, "channel")?)?.id;
+        let index = self.message_index(channel, required(request, "ts")?).map_err(|_| "thread_not_found")?;
+        let parent = &self.messages[index];
+        let thread_ts = parent.thread_ts.as_ref().unwrap_or(&parent.ts);
+        let parent = &self.messages[self.message_index(channel, thread_ts.as_str()).map_err(|_| "thread_not_found")?];
+
+        let mut messages = vec![parent];
+        messages.extend(self.replies(channel, thread_ts));
+        messages.retain(|message| in_range(request, &message.ts));
+        messages.sort_by_key(|message| ts_key(message.ts.as_str()));
+
+        let messages = messages.into_iter().map(|message| self.message_json(message)).collect();
+        let (messages, next_cursor) = page(messages, request)?;
+        Ok(json!({
+            "messages": messages,
+            "has_more": !next_cursor.is_empty(),
+            "response_metadata": { "next_cursor": next_cursor },
+        }))
+    }
+
+    fn reactions_add(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?.id.clone();
+        let name = required(request, "name")?.trim_matches(':').to_string();
+        let index = self.message_index(&channel, required(request, "timestamp")?)?;
+
+        let message = &mut self.messages[index];
+        match message.reactions.iter_mut().find(|reaction| reaction.name == name) {
+            Some(reaction) if reaction.users.contains(user) => return Err("already_reacted"),
+            Some(reaction) => {
+                reaction.count += 1;
+                reaction.users.push(user.clone());
+            }
+            None => message.reactions.push(Reaction { name: name.clone(), count: 1, users: vec![user.clone()], extra: Default::default() }),
+        }
+
+        let event = reaction_event("reaction_added", user, &name, message);
+        self.emit(event);
+        Ok(json!({}))
+    }
+
+    fn reactions_remove(&mut self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = self.visible_channel(user, required(request, "channel")?)?.id.clone();
+        let name = required(request, "name")?.trim_matches(':').to_string();
+        let index = self.message_index(&channel, required(request, "timestamp")?)?;
+
+        let message = &mut self.messages[index];
+        let position = message.reactions
+            .iter()
+            .position(|reaction| reaction.name == name && reaction.users.contains(user))
+            .ok_or("no_reaction")?;
+        let reaction = &mut message.reactions[position];
+        reaction.count -= 1;
+        reaction.users.retain(|reacted| reacted != user);
+        if reaction.users.is_empty() {
+            message.reactions.remove(position);
+        }
+
+        let event = reaction_event("reaction_removed", user, &name, message);
+        self.emit(event);
+        Ok(json!({}))
+    }
+
+    fn reactions_get(&self, user: &UserId, request: &RecordedRequest) -> Reply {
+        let channel = &self.visible_channel(user, required(request, "channel")?)?.id;
+        let index = self.message_index(channel, required(request, "timestamp")?)?;
+
+        Ok(json!({
+            "type": "message",
+            "channel": channel,
+            "message": self.message_json(&self.messages[index]),
+        }))
+    }
+
+    fn users_info(&self, request: &RecordedRequest) -> Reply {
+        let id = required(request, "user")?;
+        let user = self.users
+            .iter()
+            .find(|user| user.id.as_str() == id)
+            .ok_or("user_not_found")?;
+
+        Ok(json!({ "user": self.user_json(user) }))
+    }
+
+    fn users_list(&self, request: &RecordedRequest) -> Reply {
+        let members = self.users.iter().map(|user| self.user_json(user)).collect();
+
+        let (members, next_cursor) = page(members, request)?;
+        Ok(json!({ "members": members, "response_metadata": { "next_cursor": next_cursor } }))
+    }
+}
+
+fn required<'a>(request: &'a RecordedRequest, name: &str) -> Result<&'a str, &'static str> {
+    request.field(name).ok_or("invalid_arguments")
+}
+
+fn flag(request: &RecordedRequest, name: &str) -> bool {
+    request.field(name) == Some("true")
+}
+
+/// Whether a timestamp falls between the request's `oldest` and `latest`
+fn in_range(request: &RecordedRequest, ts: &Timestamp) -> bool {
+    let inclusive = flag(request, "inclusive");
+    let ts = ts_key(ts.as_str());
+
+    let after_oldest = request.field("oldest")
+        .map(ts_key)
+        .is_none_or(|oldest| ts > oldest || (inclusive && ts == oldest));
+    let before_latest = request.field("latest")
+        .map(ts_key)
+        .is_none_or(|latest| ts < latest || (inclusive && ts == latest));
+
+    after_oldest && before_latest
+}
+
+/// Split items into the page the request's `cursor` and `limit` ask for, along with the cursor
+/// for the next page, which is empty on the last page
+fn page(items: Vec<Value>, request: &RecordedRequest) -> Result<(Vec<Value>, String), &'static str> {
+    let start = match request.field("cursor") {
+        Some(cursor) if !cursor.is_empty() => cursor.strip_prefix("next:")
+            .and_then(|offset| offset.parse().ok())
+            .ok_or("invalid_cursor")?,
+        _ => 0,
+    };
+    let limit = match request.field("limit") {
+        Some(limit) => limit.parse().map_err(|_| "invalid_limit")?,
+        None => DEFAULT_LIMIT,
+    };
+
+    let end = items.len().min(start + limit.max(1));
+    let next_cursor = if end < items.len() {
+        format!("next:{}", end)
+    } else {
+        String::new()
+    };
+
+    Ok((items.into_iter().skip(start).take(end.saturating_sub(start)).collect(), next_cursor))
+}
+
+fn reaction_event(kind: &str, user: &UserId, name: &str, message: &FakeMessage) -> Value {
+    json!({
+        "type": kind,
+        "user": user,
+        "reaction": name,
+        "item_user": message.user,
+        "item": { "type": "message", "channel": message.channel, "ts": message.ts },
+        "event_ts": message.ts,
+    })
+}
+
+/// An in-memory Slack workspace served over HTTP. It starts with a bot user and a `#general`
+/// channel; add more users and channels, then point clients at it with [`FakeWorkspace::client`].
+/// Requests are authenticated by token, so each user sees what a real member would
+pub struct FakeWorkspace {
+    server: LocalServer,
+    state: Arc<Mutex<WorkspaceState>>,
+    bot: FakeUser,
+    general: ChannelId,
+}
+
+impl FakeWorkspace {
+    /// Start a workspace on a free local port. Must be called within a Tokio runtime
+    pub fn start() -> FakeWorkspace {
+        let mut state = WorkspaceState::new();
+        let bot = state.add_user("fakebot", true);
+        let general = state.add_channel("general", &bot.id, false);
+
+        let state = Arc::new(Mutex::new(state));
+        let handler_state = state.clone();
+        let server = LocalServer::start(Arc::new(move |request| {
+            handler_state.lock().unwrap().handle(request)
+        }));
+
+        FakeWorkspace { server, state, bot, general }
+    }
+
+    /// The base URL to give [`SlackClient::with_api_url`]
+    pub fn api_url(&self) -> String {
+        self.server.api_url()
+    }
+
+    pub fn team_id(&self) -> TeamId {
+        self.state.lock().unwrap().team_id.clone()
+    }
+
+    /// The bot user the workspace was created with
+    pub fn bot(&self) -> &FakeUser {
+        &self.bot
+    }
+
+    /// The `#general` channel, which every user joins
+    pub fn general(&self) -> &ChannelId {
+        &self.general
+    }
+
+    /// A client acting as the bot user
+    pub fn client(&self) -> SlackClient {
+        self.client_as(&self.bot)
+    }
+
+    /// A client acting as any user
+    pub fn client_as(&self, user: &FakeUser) -> SlackClient {
+        SlackClient::new(user.token.clone())
+            .with_api_url(&self.api_url())
+    }
+
+    /// Add a user with a user token, and have them join `#general`
+    pub fn add_user(&self, name: &str) -> FakeUser {
+        let mut state = self.state.lock().unwrap();
+        let user = state.add_user(name, false);
+        state.channel_mut(&self.general).members.push(user.id.clone());
+        user
+    }
+
+    /// Add a public channel, created by the bot
+    pub fn add_channel(&self, name: &str) -> ChannelId {
+        self.state.lock().unwrap().add_channel(name, &self.bot.id, false)
+    }
+
+    /// Add a private channel, created by the bot, that only its members can see
+    pub fn add_private_channel(&self, name: &str, members: &[&UserId]) -> ChannelId {
+        let mut state = self.state.lock().unwrap();
+        let id = state.add_channel(name, &self.bot.id, true);
+        state.channel_mut(&id).members.extend(members.iter().map(|&member| member.clone()));
+        id
+    }
+
+    pub fn join(&self, channel: &ChannelId, user: &UserId) {
+        let mut state = self.state.lock().unwrap();
+        let members = &mut state.channel_mut(channel).members;
+        if !members.contains(user) {
+            members.push(user.clone());
+        }
+    }
+
+    pub fn archive(&self, channel: &ChannelId) {
+        self.state.lock().unwrap().channel_mut(channel).is_archived = true;
+    }
+
+    /// Post a message as a user without going through the API, as setup for a test
+    pub fn post(&self, channel: &ChannelId, user: &UserId, text: &str) -> Timestamp {
+        self.state.lock().unwrap().post(channel, user, text, None, false).ts
+    }
+
+    /// Every message in a channel, including thread replies, in the order they were posted
+    pub fn messages(&self, channel: &ChannelId) -> Vec<FakeMessage> {
+        self.state
+            .lock()
+            .unwrap()
+            .messages
+            .iter()
+            .filter(|message| &message.channel == channel)
+            .cloned()
+            .collect()
+    }
+
+    /// Receive the Events API payload for everything that happens in the workspace from now on,
+    /// such as `message`, `reaction_added` and `channel_created` events
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Value> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.state.lock().unwrap().subscribers.push(sender);
+        receiver
+    }
+
+    /// Every request received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::Any;
+    use std::collections::HashMap;
+    use crate::Method;
+    use crate::types::{Conversation, Error, InputError, SlackError};
+
+    fn is_error<T>(result: Result<T, SlackError>, expected: Error) -> bool {
+        matches!(result, Err(SlackError::ApiError(errors)) if errors == [expected])
+    }
+
+    #[tokio::test]
+    async fn test_messages_and_threads() {
+        let workspace = FakeWorkspace::start();
+        let client = workspace.client();
+        let channel = workspace.general().clone();
+        let (text, reply) = (String::from("Deploying now"), String::from("Done!"));
+
+        let (_, parent_ts, message) = client.make_request::<Method!["chat.postMessage"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("text"), &text as &dyn Any),
+        ])).await.unwrap().into_data();
+        assert_eq!(message.text, "Deploying now");
+        assert_eq!(message.user.as_ref(), Some(&workspace.bot().id));
+
+        client.make_request::<Method!["chat.postMessage"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("text"), &reply as &dyn Any),
+            (String::from("thread_ts"), &parent_ts as &dyn Any),
+        ])).await.unwrap();
+
+        let (messages, has_more, _) = client.make_request::<Method!["conversations.history"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+        ])).await.unwrap().into_data();
+        assert!(!has_more);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].reply_count, Some(1));
+
+        let (replies, _, _) = client.make_request::<Method!["conversations.replies"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("ts"), &parent_ts as &dyn Any),
+        ])).await.unwrap().into_data();
+        let texts = replies.iter().map(|message| message.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, ["Deploying now", "Done!"]);
+        assert_eq!(replies[1].thread_ts.as_ref(), Some(&parent_ts));
+
+        let missing = ChannelId::new("C99999999");
+        assert!(is_error(client.make_request::<Method!["chat.postMessage"]>(HashMap::from([
+            (String::from("channel"), &missing as &dyn Any),
+            (String::from("text"), &text as &dyn Any),
+        ])).await, Error::ChannelNotFound));
+        // Caught by the client before it gets as far as the workspace
+        assert!(matches!(
+            client.make_request::<Method!["chat.postMessage"]>(HashMap::from([
+                (String::from("channel"), &channel as &dyn Any),
+            ])).await,
+            Err(SlackError::InvalidInput { reason: InputError::MissingOneOf(_), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_updates_and_reactions() {
+        let workspace = FakeWorkspace::start();
+        let alice = workspace.add_user("alice");
+        let mut events = workspace.subscribe();
+        let channel = workspace.general().clone();
+        let ts = workspace.post(&channel, &alice.id, "Lunch?");
+
+        let (edited, name) = (String::from("Lunch at noon?"), String::from("thumbsup"));
+        assert!(is_error(workspace.client().make_request::<Method!["chat.update"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("ts"), &ts as &dyn Any),
+            (String::from("text"), &edited as &dyn Any),
+        ])).await, Error::CantUpdateMessage));
+
+        workspace.client_as(&alice).make_request::<Method!["chat.update"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("ts"), &ts as &dyn Any),
+            (String::from("text"), &edited as &dyn Any),
+        ])).await.unwrap();
+
+        let reaction = HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("name"), &name as &dyn Any),
+            (String::from("timestamp"), &ts as &dyn Any),
+        ]);
+        let client = workspace.client();
+        client.make_request::<Method!["reactions.add"]>(reaction.clone()).await.unwrap();
+        assert!(is_error(client.make_request::<Method!["reactions.add"]>(reaction.clone()).await, Error::AlreadyReacted));
+
+        let (_, _, message, _, _) = client.make_request::<Method!["reactions.get"]>(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("timestamp"), &ts as &dyn Any),
+        ])).await.unwrap().into_data();
+        let message = message.unwrap();
+        assert_eq!(message.text, "Lunch at noon?");
+        assert_eq!(message.reactions.unwrap(), [Reaction { name: name.clone(), count: 1, users: vec![workspace.bot().id.clone()], extra: Default::default() }]);
+
+        client.make_request::<Method!["reactions.remove"]>(reaction.clone()).await.unwrap();
+        assert!(is_error(client.make_request::<Method!["reactions.remove"]>(reaction).await, Error::NoReaction));
+        assert!(workspace.messages(&channel)[0].reactions.is_empty());
+
+        let kinds = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|envelope| {
+                let event = &envelope["event"];
+                event["subtype"].as_str().unwrap_or_else(|| event["type"].as_str().unwrap()).to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["message", "message_changed", "reaction_added", "reaction_removed"]);
+    }
+
+    #[tokio::test]
+    async fn test_channels_and_users() {
+        let workspace = FakeWorkspace::start();
+        let alice = workspace.add_user("alice");
+        let bob = workspace.add_user("bob");
+        let secret = workspace.add_private_channel("secret", &[&alice.id]);
+
+        let info = HashMap::from([(String::from("channel"), &secret as &dyn Any)]);
+        let channel = workspace.client_as(&alice).make_request::<Method!["conversations.info"]>(info.clone()).await.unwrap().into_data();
+        assert!(matches!(channel, Conversation::Base(base) if base.is_private && base.is_member == Some(true)));
+        assert!(is_error(workspace.client_as(&bob).make_request::<Method!["conversations.info"]>(info).await, Error::ChannelNotFound));
+
+        let name = String::from("general");
+        assert!(is_error(workspace.client().make_request::<Method!["conversations.create"]>(HashMap::from([
+            (String::from("name"), &name as &dyn Any),
+        ])).await, Error::NameTaken));
+
+        let limit = 2u64;
+        let users = workspace.client().make_request_all::<Method!["users.list"]>(HashMap::from([
+            (String::from("limit"), &limit as &dyn Any),
+        ])).await.unwrap();
+        let names = users.data().iter().map(|user| user.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["fakebot", "alice", "bob"]);
+        assert_eq!(workspace.requests().iter().filter(|request| request.method == "users.list").count(), 2);
+
+        let missing = UserId::new("U99999999");
+        assert!(is_error(workspace.client().make_request::<Method!["users.info"]>(HashMap::from([
+            (String::from("user"), &missing as &dyn Any),
+        ])).await, Error::UserNotFound));
+    }
+}
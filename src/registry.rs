@@ -0,0 +1,156 @@
+//! Every API method defined with this crate, with what each one needs and takes, available at
+//! runtime. Useful for generating docs, building tools on top of the client, or working out the
+//! scopes an app needs from the methods it calls.
+
+use std::fmt;
+
+use crate::scopes::{Scope, ScopeSet, TokenType};
+use crate::transport::Encoding;
+use crate::types::RateLimit;
+
+/// How a method splits its results over multiple responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaginationStyle {
+    /// Opaque cursors, passed back as the `cursor` input
+    Cursor,
+    /// Page numbers, passed back as the `page` input
+    Page,
+}
+
+/// One of the inputs a method takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamInfo {
+    pub name: &'static str,
+    /// The Rust type the input is given as, as written in the method's definition
+    pub ty: &'static str,
+    /// Whether the input must always be given. Inputs in an `any_of` or `one_of` group aren't
+    /// required on their own
+    pub required: bool,
+}
+
+/// Everything known about an API method
+#[derive(Debug)]
+pub struct MethodInfo {
+    /// The API method, such as `chat.postMessage`
+    pub name: &'static str,
+    /// The names of the scopes a token needs to call the method
+    pub scopes: &'static [&'static str],
+    pub rate_limit: Option<RateLimit>,
+    pub encoding: Encoding,
+    pub params: &'static [ParamInfo],
+    /// Groups of inputs where at least one must be given
+    pub any_of: &'static [&'static [&'static str]],
+    /// Groups of inputs where exactly one must be given
+    pub one_of: &'static [&'static [&'static str]],
+    pub pagination: Option<PaginationStyle>,
+    /// Why the method shouldn't be used any more, if Slack has deprecated it
+    pub deprecated: Option<&'static str>,
+}
+
+inventory::collect!(MethodInfo);
+
+impl MethodInfo {
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .iter()
+            .map(|name| Scope::from_name(name).expect("Method definitions only use known scopes"))
+            .collect()
+    }
+
+    /// The kinds of token that can call the method, being those that can be granted any of its
+    /// scopes
+    pub fn token_types(&self) -> Vec<TokenType> {
+        let scopes = self.scopes();
+
+        // Methods that need no scopes, like `views.open`, check something else such as a trigger
+        if scopes.is_empty() {
+            return vec![TokenType::Bot, TokenType::User];
+        }
+
+        [TokenType::App, TokenType::Bot, TokenType::User]
+            .iter()
+            .copied()
+            .filter(|ty| scopes.iter().any(|scope| scope.token_types().contains(ty)))
+            .collect()
+    }
+
+    pub fn param(&self, name: &str) -> Option<&'static ParamInfo> {
+        self.params.iter().find(|param| param.name == name)
+    }
+}
+
+/// Every method, sorted by name
+pub fn methods() -> Vec<&'static MethodInfo> {
+    let mut methods = inventory::iter::<MethodInfo>.into_iter().collect::<Vec<_>>();
+    methods.sort_by_key(|method| method.name);
+    methods
+}
+
+/// Look up a method by its API name, such as `chat.postMessage`
+pub fn method(name: &str) -> Option<&'static MethodInfo> {
+    inventory::iter::<MethodInfo>
+        .into_iter()
+        .find(|method| method.name == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownMethod(pub String);
+
+impl fmt::Display for UnknownMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown method `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownMethod {}
+
+/// Every scope that any of `names` can be called with. Each method's scopes are alternatives,
+/// so this is more than an app strictly needs when it only uses one kind of token
+pub fn required_scopes<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<ScopeSet, UnknownMethod> {
+    let mut scopes = ScopeSet::new();
+
+    for name in names {
+        let method = method(name).ok_or_else(|| UnknownMethod(name.to_string()))?;
+        for scope in method.scopes() {
+            scopes.insert(scope);
+        }
+    }
+
+    Ok(scopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let names = methods().iter().map(|method| method.name).collect::<Vec<_>>();
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(names.contains(&"conversations.list") && names.contains(&"views.open"));
+
+        let post = method("chat.postMessage").unwrap();
+        assert_eq!(post.scopes, ["chat:write"]);
+        assert_eq!(post.token_types(), [TokenType::Bot, TokenType::User]);
+        assert_eq!(post.rate_limit, Some(RateLimit::Tier4));
+        assert_eq!(post.encoding, Encoding::Json);
+        assert_eq!(post.param("channel"), Some(&ParamInfo { name: "channel", ty: "ChannelId", required: true }));
+        assert!(!post.param("text").unwrap().required);
+        assert_eq!(method("conversations.archive").unwrap().token_types(), [TokenType::Bot, TokenType::User]);
+        assert_eq!(post.any_of, [&["text", "blocks", "attachments"]]);
+        assert_eq!(post.pagination, None);
+
+        assert_eq!(method("files.list").unwrap().pagination, Some(PaginationStyle::Page));
+        assert_eq!(method("users.list").unwrap().pagination, Some(PaginationStyle::Cursor));
+        assert_eq!(method("views.open").unwrap().one_of, [&["trigger_id", "interactivity_pointer"]]);
+        assert!(method("chat.postMessages").is_none());
+    }
+
+    #[test]
+    fn test_required_scopes() {
+        let scopes = required_scopes(["chat.postMessage", "reactions.add", "users.list"]).unwrap();
+        assert_eq!(scopes.to_string(), "chat:write,reactions:write,users:read");
+
+        assert_eq!(required_scopes(["chat.send"]), Err(UnknownMethod(String::from("chat.send"))));
+    }
+}
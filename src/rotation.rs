@@ -0,0 +1,127 @@
+use std::sync::{Arc, RwLock};
+
+use http::header::HeaderMap;
+use tokio::sync::Mutex;
+
+use crate::oauth::{unix_now, OAuthClient, RefreshedToken};
+use crate::scopes::{ScopeSet, TokenType};
+use crate::types::{SlackError, Token};
+
+pub(crate) type RefreshCallback = Box<dyn Fn(&RefreshedToken) + Send + Sync>;
+
+struct RotationState {
+    refresh_token: String,
+    expires_at: u64,
+}
+
+/// Keeps a rotating access token fresh, swapping it out shortly before it expires
+pub(crate) struct TokenRotation {
+    oauth: Arc<OAuthClient>,
+    // Held across the refresh call, so concurrent requests don't all spend the refresh token
+    state: Mutex<RotationState>,
+    on_refresh: RefreshCallback,
+}
+
+impl TokenRotation {
+    /// How long before expiry to refresh, in seconds
+    const EXPIRY_MARGIN: u64 = 300;
+
+    pub(crate) fn new(oauth: Arc<OAuthClient>, refresh_token: &str, expires_at: u64, on_refresh: RefreshCallback) -> TokenRotation {
+        TokenRotation {
+            oauth,
+            state: Mutex::new(RotationState {
+                refresh_token: refresh_token.to_string(),
+                expires_at,
+            }),
+            on_refresh,
+        }
+    }
+
+    /// Refresh `token` if it's about to expire, or if it's still `rejected` after Slack reported
+    /// it as expired
+    pub(crate) async fn ensure_fresh(&self, token: &RwLock<Token>, rejected: Option<&Token>) -> Result<(), SlackError> {
+        let mut state = self.state.lock().await;
+
+        let stale = match rejected {
+            Some(rejected) => *token.read().unwrap() == *rejected,
+            None => state.expires_at <= unix_now() + Self::EXPIRY_MARGIN,
+        };
+        if !stale {
+            return Ok(());
+        }
+
+        let refreshed = self.oauth.refresh_token(&state.refresh_token).await?;
+        let access_token = Token::parse(&refreshed.access_token);
+
+        // The old refresh token is spent, so the new one has to be kept even if the access token
+        // is unusable. The expiry is left alone in that case, so the next request tries again
+        (self.on_refresh)(&refreshed);
+        state.refresh_token = refreshed.refresh_token.clone();
+
+        *token.write().unwrap() = access_token.ok_or(SlackError::InvalidToken)?;
+        state.expires_at = refreshed.expires_at;
+        Ok(())
+    }
+}
+
+/// One of the tokens a client holds, and how to keep it fresh
+pub(crate) struct TokenSlot {
+    token: RwLock<Token>,
+    pub(crate) rotation: Option<TokenRotation>,
+    granted: RwLock<Option<ScopeSet>>,
+}
+
+impl TokenSlot {
+    pub(crate) fn new(token: Token) -> TokenSlot {
+        TokenSlot {
+            token: RwLock::new(token),
+            rotation: None,
+            granted: RwLock::new(None),
+        }
+    }
+
+    /// The scopes Slack last said this token has, if it's said yet
+    pub(crate) fn granted_scopes(&self) -> Option<ScopeSet> {
+        self.granted.read().unwrap().clone()
+    }
+
+    /// Remember the scopes from a response's `x-oauth-scopes` header, if it has one
+    pub(crate) fn record_granted_scopes(&self, headers: &HeaderMap) {
+        let scopes = headers.get("x-oauth-scopes")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        if let Some(scopes) = scopes {
+            *self.granted.write().unwrap() = Some(scopes);
+        }
+    }
+
+    pub(crate) fn token_type(&self) -> Option<TokenType> {
+        self.token.read().unwrap().token_type()
+    }
+
+    pub(crate) fn token(&self) -> Token {
+        self.token.read().unwrap().clone()
+    }
+
+    /// The token to send with the next request, refreshing it first if it's about to expire
+    pub(crate) async fn fresh_token(&self) -> Result<Token, SlackError> {
+        if let Some(rotation) = &self.rotation {
+            rotation.ensure_fresh(&self.token, None).await?;
+        }
+
+        Ok(self.token())
+    }
+
+    /// Refresh the token after Slack rejected `rejected` as expired. Returns `false` if this slot
+    /// doesn't rotate, so there's nothing to be done
+    pub(crate) async fn refresh_expired(&self, rejected: &Token) -> Result<bool, SlackError> {
+        match &self.rotation {
+            Some(rotation) => {
+                rotation.ensure_fresh(&self.token, Some(rejected)).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
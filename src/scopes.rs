@@ -1,74 +1,271 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error as SerdeError;
+
 use TokenType::{App, Bot, User};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenType {
     App,
     Bot,
     User,
 }
 
+/// An OAuth scope, and the kinds of token that can be granted it. Scopes are compared by name
+#[derive(Debug, Clone, Copy)]
 pub struct Scope {
     name: &'static str,
     valid_tokens: &'static [TokenType]
 }
 
-#[doc(hidden)]
 impl Scope {
-    const ADMIN: Scope = Scope::new("admin", &[User]);
-    const ADMIN_ANALYTICS_READ: Scope = Scope::new("admin.analytics:read", &[User]);
-    const ADMIN_APPS_READ: Scope = Scope::new("admin.apps:read", &[User]);
-    const ADMIN_APPS_WRITE: Scope = Scope::new("admin.apps:write", &[User]);
-    const ADMIN_BARRIERS_READ: Scope = Scope::new("admin.barriers:read", &[User]);
-    const ADMIN_BARRIERS_WRITE: Scope = Scope::new("admin.barriers:write", &[User]);
-    const ADMIN_CONVERSATIONS_READ: Scope = Scope::new("admin.conversations:read", &[User]);
-    const ADMIN_CONVERSATIONS_WRITE: Scope = Scope::new("admin.conversations:write", &[User]);
-    const ADMIN_INVITES_READ: Scope = Scope::new("admin.invites:read", &[User]);
-    const ADMIN_INVITES_WRITE: Scope = Scope::new("admin.invites:write", &[User]);
-    const ADMIN_TEAMS_READ: Scope = Scope::new("admin.teams:read", &[User]);
-    const ADMIN_TEAMS_WRITE: Scope = Scope::new("admin.teams:write", &[User]);
-    const ADMIN_USERGROUPS_READ: Scope = Scope::new("admin.usergroups:read", &[User]);
-    const ADMIN_USERGROUPS_WRITE: Scope = Scope::new("admin.uesrgroups:write", &[User]);
-    const ADMIN_USERS_READ: Scope = Scope::new("admin.users:read", &[User]);
-    const ADMIN_USERS_WRITE: Scope = Scope::new("admin.users:write", &[User]);
-    const APP_MENTIONS_READ: Scope = Scope::new("app_mentions:read", &[Bot]);
-    const AUDITLOGS_READ: Scope = Scope::new("auditlogs:read", &[User]);
-    const AUTHORIZATIONS_READ: Scope = Scope::new("authorizations:read", &[App]);
-    const CALLS_READ: Scope = Scope::new("calls:read", &[Bot, User]);
-    const CALLS_WRITE: Scope = Scope::new("calls:write", &[Bot, User]);
-    const CHANNELS_HISTORY: Scope = Scope::new("channels:history", &[Bot, User]);
-    const CHANNELS_JOIN: Scope = Scope::new("channels:join", &[Bot]);
-    const CHANNELS_MANAGE: Scope = Scope::new("channels:manage", &[Bot]);
-    const CHANNELS_READ: Scope = Scope::new("channels:read", &[Bot, User]);
-    const CHANNELS_WRITE: Scope = Scope::new("channels:write", &[User]);
-    const CHAT_WRITE: Scope = Scope::new("chat:write", &[Bot, User]);
-    const CHAT_WRITE_CUSTOMIZE: Scope = Scope::new("chat:write.customize", &[Bot]);
-    const CHAT_WRITE_PUBLIC: Scope = Scope::new("chat:write.public", &[Bot]);
-    const CHAT_WRITE_BOT: Scope = Scope::new("chat:write:bot", &[User]);
-    const CHAT_WRITE_USER: Scope = Scope::new("chat:write:user", &[User]);
-    const COMMANDS: Scope = Scope::new("commands", &[Bot, User]);
-    const CONNECTIONS_WRITE: Scope = Scope::new("connections:write", &[App]);
-    const CONVERSATIONS_CONNECT_MANNAGE: Scope = Scope::new("conversations.connect:manage", &[Bot]);
-    const CONVERSATIONS_CONNECT_READ: Scope = Scope::new("conversations.connect:read", &[Bot]);
-    const CONVERSATIONS_CONNECT_WRITE: Scope = Scope::new("conversations.connect:write", &[Bot]);
-    const GROUPS_READ: Scope = Scope::new("groups:read", &[Bot, User]);
-    const IM_READ: Scope = Scope::new("im:read", &[Bot, User]);
-    const MPIM_READ: Scope = Scope::new("mpim:read", &[Bot, User]);
+    pub const ADMIN: Scope = Scope::new("admin", &[User]);
+    pub const ADMIN_ANALYTICS_READ: Scope = Scope::new("admin.analytics:read", &[User]);
+    pub const ADMIN_APP_ACTIVITIES_READ: Scope = Scope::new("admin.app_activities:read", &[User]);
+    pub const ADMIN_APPS_READ: Scope = Scope::new("admin.apps:read", &[User]);
+    pub const ADMIN_APPS_WRITE: Scope = Scope::new("admin.apps:write", &[User]);
+    pub const ADMIN_BARRIERS_READ: Scope = Scope::new("admin.barriers:read", &[User]);
+    pub const ADMIN_BARRIERS_WRITE: Scope = Scope::new("admin.barriers:write", &[User]);
+    pub const ADMIN_CHAT_READ: Scope = Scope::new("admin.chat:read", &[User]);
+    pub const ADMIN_CHAT_WRITE: Scope = Scope::new("admin.chat:write", &[User]);
+    pub const ADMIN_CONVERSATIONS_MANAGE_OBJECTS: Scope = Scope::new("admin.conversations:manage_objects", &[User]);
+    pub const ADMIN_CONVERSATIONS_READ: Scope = Scope::new("admin.conversations:read", &[User]);
+    pub const ADMIN_CONVERSATIONS_WRITE: Scope = Scope::new("admin.conversations:write", &[User]);
+    pub const ADMIN_INVITES_READ: Scope = Scope::new("admin.invites:read", &[User]);
+    pub const ADMIN_INVITES_WRITE: Scope = Scope::new("admin.invites:write", &[User]);
+    pub const ADMIN_ROLES_READ: Scope = Scope::new("admin.roles:read", &[User]);
+    pub const ADMIN_ROLES_WRITE: Scope = Scope::new("admin.roles:write", &[User]);
+    pub const ADMIN_TEAMS_READ: Scope = Scope::new("admin.teams:read", &[User]);
+    pub const ADMIN_TEAMS_WRITE: Scope = Scope::new("admin.teams:write", &[User]);
+    pub const ADMIN_USERGROUPS_READ: Scope = Scope::new("admin.usergroups:read", &[User]);
+    pub const ADMIN_USERGROUPS_WRITE: Scope = Scope::new("admin.usergroups:write", &[User]);
+    pub const ADMIN_USERS_READ: Scope = Scope::new("admin.users:read", &[User]);
+    pub const ADMIN_USERS_WRITE: Scope = Scope::new("admin.users:write", &[User]);
+    pub const ADMIN_WORKFLOWS_READ: Scope = Scope::new("admin.workflows:read", &[User]);
+    pub const ADMIN_WORKFLOWS_WRITE: Scope = Scope::new("admin.workflows:write", &[User]);
+    pub const APP_MENTIONS_READ: Scope = Scope::new("app_mentions:read", &[Bot]);
+    pub const AUDITLOGS_READ: Scope = Scope::new("auditlogs:read", &[User]);
+    pub const AUTHORIZATIONS_READ: Scope = Scope::new("authorizations:read", &[App]);
+    pub const BOOKMARKS_READ: Scope = Scope::new("bookmarks:read", &[Bot, User]);
+    pub const BOOKMARKS_WRITE: Scope = Scope::new("bookmarks:write", &[Bot, User]);
+    pub const CALLS_READ: Scope = Scope::new("calls:read", &[Bot, User]);
+    pub const CALLS_WRITE: Scope = Scope::new("calls:write", &[Bot, User]);
+    pub const CANVASES_READ: Scope = Scope::new("canvases:read", &[Bot, User]);
+    pub const CANVASES_WRITE: Scope = Scope::new("canvases:write", &[Bot, User]);
+    pub const CHANNELS_HISTORY: Scope = Scope::new("channels:history", &[Bot, User]);
+    pub const CHANNELS_JOIN: Scope = Scope::new("channels:join", &[Bot]);
+    pub const CHANNELS_MANAGE: Scope = Scope::new("channels:manage", &[Bot]);
+    pub const CHANNELS_READ: Scope = Scope::new("channels:read", &[Bot, User]);
+    pub const CHANNELS_WRITE: Scope = Scope::new("channels:write", &[User]);
+    pub const CHANNELS_WRITE_INVITES: Scope = Scope::new("channels:write.invites", &[Bot, User]);
+    pub const CHANNELS_WRITE_TOPIC: Scope = Scope::new("channels:write.topic", &[Bot, User]);
+    pub const CHAT_WRITE: Scope = Scope::new("chat:write", &[Bot, User]);
+    pub const CHAT_WRITE_CUSTOMIZE: Scope = Scope::new("chat:write.customize", &[Bot]);
+    pub const CHAT_WRITE_PUBLIC: Scope = Scope::new("chat:write.public", &[Bot]);
+    pub const CHAT_WRITE_BOT: Scope = Scope::new("chat:write:bot", &[User]);
+    pub const CHAT_WRITE_USER: Scope = Scope::new("chat:write:user", &[User]);
+    pub const CLIENT: Scope = Scope::new("client", &[User]);
+    pub const COMMANDS: Scope = Scope::new("commands", &[Bot, User]);
+    pub const CONNECTIONS_WRITE: Scope = Scope::new("connections:write", &[App]);
+    pub const CONVERSATIONS_CONNECT_MANAGE: Scope = Scope::new("conversations.connect:manage", &[Bot]);
+    pub const CONVERSATIONS_CONNECT_READ: Scope = Scope::new("conversations.connect:read", &[Bot]);
+    pub const CONVERSATIONS_CONNECT_WRITE: Scope = Scope::new("conversations.connect:write", &[Bot]);
+    pub const DND_READ: Scope = Scope::new("dnd:read", &[Bot, User]);
+    pub const DND_WRITE: Scope = Scope::new("dnd:write", &[User]);
+    pub const EMAIL: Scope = Scope::new("email", &[User]);
+    pub const EMOJI_READ: Scope = Scope::new("emoji:read", &[Bot, User]);
+    pub const FILES_READ: Scope = Scope::new("files:read", &[Bot, User]);
+    pub const FILES_WRITE: Scope = Scope::new("files:write", &[Bot, User]);
+    pub const FILES_WRITE_USER: Scope = Scope::new("files:write:user", &[User]);
+    pub const GROUPS_HISTORY: Scope = Scope::new("groups:history", &[Bot, User]);
+    pub const GROUPS_READ: Scope = Scope::new("groups:read", &[Bot, User]);
+    pub const GROUPS_WRITE: Scope = Scope::new("groups:write", &[Bot, User]);
+    pub const GROUPS_WRITE_INVITES: Scope = Scope::new("groups:write.invites", &[Bot, User]);
+    pub const GROUPS_WRITE_TOPIC: Scope = Scope::new("groups:write.topic", &[Bot, User]);
+    pub const IDENTIFY: Scope = Scope::new("identify", &[User]);
+    pub const IDENTITY_AVATAR: Scope = Scope::new("identity.avatar", &[User]);
+    pub const IDENTITY_BASIC: Scope = Scope::new("identity.basic", &[User]);
+    pub const IDENTITY_EMAIL: Scope = Scope::new("identity.email", &[User]);
+    pub const IDENTITY_TEAM: Scope = Scope::new("identity.team", &[User]);
+    pub const IM_HISTORY: Scope = Scope::new("im:history", &[Bot, User]);
+    pub const IM_READ: Scope = Scope::new("im:read", &[Bot, User]);
+    pub const IM_WRITE: Scope = Scope::new("im:write", &[Bot, User]);
+    pub const IM_WRITE_TOPIC: Scope = Scope::new("im:write.topic", &[Bot, User]);
+    pub const INCOMING_WEBHOOK: Scope = Scope::new("incoming-webhook", &[Bot, User]);
+    pub const LINKS_EMBED_WRITE: Scope = Scope::new("links.embed:write", &[Bot, User]);
+    pub const LINKS_READ: Scope = Scope::new("links:read", &[Bot, User]);
+    pub const LINKS_WRITE: Scope = Scope::new("links:write", &[Bot, User]);
+    pub const METADATA_MESSAGE_READ: Scope = Scope::new("metadata.message:read", &[Bot, User]);
+    pub const MPIM_HISTORY: Scope = Scope::new("mpim:history", &[Bot, User]);
+    pub const MPIM_READ: Scope = Scope::new("mpim:read", &[Bot, User]);
+    pub const MPIM_WRITE: Scope = Scope::new("mpim:write", &[Bot, User]);
+    pub const MPIM_WRITE_TOPIC: Scope = Scope::new("mpim:write.topic", &[Bot, User]);
+    pub const OPENID: Scope = Scope::new("openid", &[User]);
+    pub const PINS_READ: Scope = Scope::new("pins:read", &[Bot, User]);
+    pub const PINS_WRITE: Scope = Scope::new("pins:write", &[Bot, User]);
+    pub const PROFILE: Scope = Scope::new("profile", &[User]);
+    pub const REACTIONS_READ: Scope = Scope::new("reactions:read", &[Bot, User]);
+    pub const REACTIONS_WRITE: Scope = Scope::new("reactions:write", &[Bot, User]);
+    pub const REMINDERS_READ: Scope = Scope::new("reminders:read", &[Bot, User]);
+    pub const REMINDERS_WRITE: Scope = Scope::new("reminders:write", &[Bot, User]);
+    pub const REMOTE_FILES_READ: Scope = Scope::new("remote_files:read", &[Bot]);
+    pub const REMOTE_FILES_SHARE: Scope = Scope::new("remote_files:share", &[Bot]);
+    pub const REMOTE_FILES_WRITE: Scope = Scope::new("remote_files:write", &[Bot]);
+    pub const SEARCH_READ: Scope = Scope::new("search:read", &[User]);
+    pub const STARS_READ: Scope = Scope::new("stars:read", &[User]);
+    pub const STARS_WRITE: Scope = Scope::new("stars:write", &[User]);
+    pub const TEAM_BILLING_READ: Scope = Scope::new("team.billing:read", &[Bot, User]);
+    pub const TEAM_PREFERENCES_READ: Scope = Scope::new("team.preferences:read", &[Bot, User]);
+    pub const TEAM_READ: Scope = Scope::new("team:read", &[Bot, User]);
+    pub const TOKENS_BASIC: Scope = Scope::new("tokens.basic", &[User]);
+    pub const TRIGGERS_READ: Scope = Scope::new("triggers:read", &[Bot, User]);
+    pub const TRIGGERS_WRITE: Scope = Scope::new("triggers:write", &[Bot, User]);
+    pub const USERGROUPS_READ: Scope = Scope::new("usergroups:read", &[Bot, User]);
+    pub const USERGROUPS_WRITE: Scope = Scope::new("usergroups:write", &[Bot, User]);
+    pub const USERS_PROFILE_READ: Scope = Scope::new("users.profile:read", &[Bot, User]);
+    pub const USERS_PROFILE_WRITE: Scope = Scope::new("users.profile:write", &[User]);
+    pub const USERS_READ: Scope = Scope::new("users:read", &[Bot, User]);
+    pub const USERS_READ_EMAIL: Scope = Scope::new("users:read.email", &[Bot, User]);
+    pub const USERS_WRITE: Scope = Scope::new("users:write", &[Bot, User]);
+    pub const WORKFLOW_STEPS_EXECUTE: Scope = Scope::new("workflow.steps:execute", &[Bot]);
+
+    /// Every scope this crate knows about, which covers those used by the methods it defines and
+    /// by the vendored API spec. Slack adds scopes over time, so it isn't guaranteed to be
+    /// complete; a [`ScopeSet`] keeps any names it doesn't recognise
+    pub const ALL: &'static [Scope] = &[
+        Scope::ADMIN,
+        Scope::ADMIN_ANALYTICS_READ,
+        Scope::ADMIN_APP_ACTIVITIES_READ,
+        Scope::ADMIN_APPS_READ,
+        Scope::ADMIN_APPS_WRITE,
+        Scope::ADMIN_BARRIERS_READ,
+        Scope::ADMIN_BARRIERS_WRITE,
+        Scope::ADMIN_CHAT_READ,
+        Scope::ADMIN_CHAT_WRITE,
+        Scope::ADMIN_CONVERSATIONS_MANAGE_OBJECTS,
+        Scope::ADMIN_CONVERSATIONS_READ,
+        Scope::ADMIN_CONVERSATIONS_WRITE,
+        Scope::ADMIN_INVITES_READ,
+        Scope::ADMIN_INVITES_WRITE,
+        Scope::ADMIN_ROLES_READ,
+        Scope::ADMIN_ROLES_WRITE,
+        Scope::ADMIN_TEAMS_READ,
+        Scope::ADMIN_TEAMS_WRITE,
+        Scope::ADMIN_USERGROUPS_READ,
+        Scope::ADMIN_USERGROUPS_WRITE,
+        Scope::ADMIN_USERS_READ,
+        Scope::ADMIN_USERS_WRITE,
+        Scope::ADMIN_WORKFLOWS_READ,
+        Scope::ADMIN_WORKFLOWS_WRITE,
+        Scope::APP_MENTIONS_READ,
+        Scope::AUDITLOGS_READ,
+        Scope::AUTHORIZATIONS_READ,
+        Scope::BOOKMARKS_READ,
+        Scope::BOOKMARKS_WRITE,
+        Scope::CALLS_READ,
+        Scope::CALLS_WRITE,
+        Scope::CANVASES_READ,
+        Scope::CANVASES_WRITE,
+        Scope::CHANNELS_HISTORY,
+        Scope::CHANNELS_JOIN,
+        Scope::CHANNELS_MANAGE,
+        Scope::CHANNELS_READ,
+        Scope::CHANNELS_WRITE,
+        Scope::CHANNELS_WRITE_INVITES,
+        Scope::CHANNELS_WRITE_TOPIC,
+        Scope::CHAT_WRITE,
+        Scope::CHAT_WRITE_CUSTOMIZE,
+        Scope::CHAT_WRITE_PUBLIC,
+        Scope::CHAT_WRITE_BOT,
+        Scope::CHAT_WRITE_USER,
+        Scope::CLIENT,
+        Scope::COMMANDS,
+        Scope::CONNECTIONS_WRITE,
+        Scope::CONVERSATIONS_CONNECT_MANAGE,
+        Scope::CONVERSATIONS_CONNECT_READ,
+        Scope::CONVERSATIONS_CONNECT_WRITE,
+        Scope::DND_READ,
+        Scope::DND_WRITE,
+        Scope::EMAIL,
+        Scope::EMOJI_READ,
+        Scope::FILES_READ,
+        Scope::FILES_WRITE,
+        Scope::FILES_WRITE_USER,
+        Scope::GROUPS_HISTORY,
+        Scope::GROUPS_READ,
+        Scope::GROUPS_WRITE,
+        Scope::GROUPS_WRITE_INVITES,
+        Scope::GROUPS_WRITE_TOPIC,
+        Scope::IDENTIFY,
+        Scope::IDENTITY_AVATAR,
+        Scope::IDENTITY_BASIC,
+        Scope::IDENTITY_EMAIL,
+        Scope::IDENTITY_TEAM,
+        Scope::IM_HISTORY,
+        Scope::IM_READ,
+        Scope::IM_WRITE,
+        Scope::IM_WRITE_TOPIC,
+        Scope::INCOMING_WEBHOOK,
+        Scope::LINKS_EMBED_WRITE,
+        Scope::LINKS_READ,
+        Scope::LINKS_WRITE,
+        Scope::METADATA_MESSAGE_READ,
+        Scope::MPIM_HISTORY,
+        Scope::MPIM_READ,
+        Scope::MPIM_WRITE,
+        Scope::MPIM_WRITE_TOPIC,
+        Scope::OPENID,
+        Scope::PINS_READ,
+        Scope::PINS_WRITE,
+        Scope::PROFILE,
+        Scope::REACTIONS_READ,
+        Scope::REACTIONS_WRITE,
+        Scope::REMINDERS_READ,
+        Scope::REMINDERS_WRITE,
+        Scope::REMOTE_FILES_READ,
+        Scope::REMOTE_FILES_SHARE,
+        Scope::REMOTE_FILES_WRITE,
+        Scope::SEARCH_READ,
+        Scope::STARS_READ,
+        Scope::STARS_WRITE,
+        Scope::TEAM_BILLING_READ,
+        Scope::TEAM_PREFERENCES_READ,
+        Scope::TEAM_READ,
+        Scope::TOKENS_BASIC,
+        Scope::TRIGGERS_READ,
+        Scope::TRIGGERS_WRITE,
+        Scope::USERGROUPS_READ,
+        Scope::USERGROUPS_WRITE,
+        Scope::USERS_PROFILE_READ,
+        Scope::USERS_PROFILE_WRITE,
+        Scope::USERS_READ,
+        Scope::USERS_READ_EMAIL,
+        Scope::USERS_WRITE,
+        Scope::WORKFLOW_STEPS_EXECUTE,
+    ];
 
     const fn new(name: &'static str, valid_tokens: &'static [TokenType]) -> Scope {
         Scope { name, valid_tokens }
     }
 
-    pub fn from_name(name: &'static str) -> Scope {
-        match name {
-            "admin" => Self::ADMIN,
-            "admin.analytics:read" => Self::ADMIN_ANALYTICS_READ,
-            "admin.apps:read" => Self::ADMIN_APPS_READ,
-            "admin.apps:write" => Self::ADMIN_APPS_WRITE,
-            "channels:read" => Self::CHANNELS_READ,
-            "groups:read" => Self::GROUPS_READ,
-            "im:read" => Self::IM_READ,
-            "mpim:read" => Self::MPIM_READ,
-            _ => panic!("Unrecognized scope name"),
+    /// Look up a scope by name, or `None` if it isn't one of [`Scope::ALL`]. It's a `const fn` so
+    /// method definitions can check their scopes when they're compiled
+    pub const fn from_name(name: &str) -> Option<Scope> {
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            if same_name(Self::ALL[i].name, name) {
+                return Some(Self::ALL[i]);
+            }
+            i += 1;
         }
+        None
     }
 
     pub fn name(&self) -> &'static str {
@@ -80,11 +277,241 @@ impl Scope {
     }
 }
 
-pub enum OldScope {
-    Admin,
-    AdminAnalyticsRead,
-    ChannelsRead,
-    GroupsRead,
-    ImRead,
-    MpimRead,
+const fn same_name(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl PartialEq for Scope {
+    fn eq(&self, other: &Scope) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Scope {}
+
+impl Hash for Scope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
+    }
+}
+
+impl PartialOrd for Scope {
+    fn partial_cmp(&self, other: &Scope) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scope {
+    fn cmp(&self, other: &Scope) -> Ordering {
+        self.name.cmp(other.name)
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownScope(pub String);
+
+impl fmt::Display for UnknownScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown scope `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownScope {}
+
+impl FromStr for Scope {
+    type Err = UnknownScope;
+
+    fn from_str(str: &str) -> Result<Scope, UnknownScope> {
+        Scope::from_name(str).ok_or_else(|| UnknownScope(str.to_string()))
+    }
+}
+
+impl Serialize for Scope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scope {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Scope, D::Error> {
+        String::deserialize(de)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A set of scopes, such as those granted to a token. Scope names this crate doesn't know about
+/// are kept, so nothing Slack sends is lost, but are only visible through [`ScopeSet::unknown`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeSet {
+    names: BTreeSet<String>,
+}
+
+impl ScopeSet {
+    pub fn new() -> ScopeSet {
+        ScopeSet::default()
+    }
+
+    pub fn insert(&mut self, scope: Scope) {
+        self.names.insert(scope.name.to_string());
+    }
+
+    pub fn contains(&self, scope: &Scope) -> bool {
+        self.names.contains(scope.name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The known scopes in this set
+    pub fn iter(&self) -> impl Iterator<Item = Scope> + '_ {
+        self.names.iter().filter_map(|name| Scope::from_name(name))
+    }
+
+    /// The names in this set that aren't scopes this crate knows about
+    pub fn unknown(&self) -> impl Iterator<Item = &str> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| Scope::from_name(name).is_none())
+    }
+
+    /// The scopes in this set but not in `other`
+    pub fn difference(&self, other: &ScopeSet) -> ScopeSet {
+        ScopeSet {
+            names: self.names.difference(&other.names).cloned().collect(),
+        }
+    }
+
+    pub fn union(&self, other: &ScopeSet) -> ScopeSet {
+        ScopeSet {
+            names: self.names.union(&other.names).cloned().collect(),
+        }
+    }
+
+    /// Which of `required` aren't in this set
+    pub fn missing(&self, required: &[Scope]) -> Vec<Scope> {
+        required.iter()
+            .filter(|scope| !self.contains(scope))
+            .copied()
+            .collect()
+    }
+}
+
+/// Parses the comma or space separated lists used by OAuth responses and the `x-oauth-scopes`
+/// header
+impl FromStr for ScopeSet {
+    type Err = Infallible;
+
+    fn from_str(str: &str) -> Result<ScopeSet, Infallible> {
+        Ok(ScopeSet {
+            names: str.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for ScopeSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.names.iter().map(String::as_str).collect::<Vec<_>>();
+        f.write_str(&names.join(","))
+    }
+}
+
+impl FromIterator<Scope> for ScopeSet {
+    fn from_iter<I: IntoIterator<Item = Scope>>(iter: I) -> ScopeSet {
+        ScopeSet {
+            names: iter.into_iter().map(|scope| scope.name.to_string()).collect(),
+        }
+    }
+}
+
+impl Serialize for ScopeSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScopeSet {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<ScopeSet, D::Error> {
+        let Ok(scopes) = String::deserialize(de)?.parse();
+        Ok(scopes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!("chat:write".parse(), Ok(Scope::CHAT_WRITE));
+        assert_eq!("admin.usergroups:write".parse::<Scope>().unwrap().name(), "admin.usergroups:write");
+        assert_eq!("chat:shout".parse::<Scope>(), Err(UnknownScope(String::from("chat:shout"))));
+
+        let names = Scope::ALL.iter().map(Scope::name).collect::<BTreeSet<_>>();
+        assert_eq!(names.len(), Scope::ALL.len(), "Scope names should be unique");
+    }
+
+    #[test]
+    fn test_known_scopes() {
+        let spec: serde_json::Value = serde_json::from_str(include_str!("../spec/slack_web.json")).unwrap();
+        let spec_scopes = spec["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .flat_map(|path| path.as_object().unwrap().values())
+            .flat_map(|operation| operation["security"].as_array().cloned().unwrap_or_default())
+            .flat_map(|scheme| scheme.as_object().unwrap().values().cloned().collect::<Vec<_>>())
+            .flat_map(|scopes| scopes.as_array().cloned().unwrap_or_default())
+            .map(|scope| scope.as_str().unwrap().to_string());
+
+        let defined_scopes = crate::registry::methods()
+            .into_iter()
+            .flat_map(|method| method.scopes.iter())
+            .map(|scope| scope.to_string());
+
+        for name in spec_scopes.chain(defined_scopes) {
+            assert!(Scope::from_name(&name).is_some(), "`{}` is missing from Scope::ALL", name);
+        }
+    }
+
+    #[test]
+    fn test_scope_set() {
+        let granted: ScopeSet = "chat:write, channels:read commands,future:scope".parse().unwrap();
+        assert_eq!(granted.len(), 4);
+        assert!(granted.contains(&Scope::COMMANDS));
+        assert_eq!(granted.unknown().collect::<Vec<_>>(), ["future:scope"]);
+
+        let wanted = [Scope::CHAT_WRITE, Scope::USERS_READ].iter().copied().collect::<ScopeSet>();
+        assert_eq!(wanted.difference(&granted).iter().collect::<Vec<_>>(), [Scope::USERS_READ]);
+        assert_eq!(granted.missing(&[Scope::CHAT_WRITE, Scope::FILES_READ]), [Scope::FILES_READ]);
+        assert_eq!(granted.union(&wanted).len(), 5);
+        assert_eq!(wanted.to_string(), "chat:write,users:read");
+    }
 }
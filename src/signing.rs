@@ -0,0 +1,154 @@
+//! Checking that requests to our endpoints really came from Slack. Slack signs every event,
+//! slash command and interaction with the app's signing secret: the signature is an HMAC-SHA256
+//! of `v0:<timestamp>:<body>`, sent hex encoded as `v0=...` in the `X-Slack-Signature` header.
+
+use hmac::{Hmac, Mac};
+use http::header::HeaderMap;
+use sha2::Sha256;
+
+use crate::oauth::unix_now;
+
+pub const SIGNATURE_HEADER: &str = "x-slack-signature";
+pub const TIMESTAMP_HEADER: &str = "x-slack-request-timestamp";
+
+const VERSION: &str = "v0";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The request is missing the signature or timestamp header
+    MissingHeader(&'static str),
+    /// The timestamp header isn't a number of seconds
+    InvalidTimestamp,
+    /// The request was signed too long ago, so it may be a replay
+    Expired,
+    /// The signature doesn't match the body
+    InvalidSignature,
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::MissingHeader(header) => write!(f, "Missing `{}` header", header),
+            VerifyError::InvalidTimestamp => f.write_str("Request timestamp isn't a number"),
+            VerifyError::Expired => f.write_str("Request timestamp is too old"),
+            VerifyError::InvalidSignature => f.write_str("Request signature doesn't match"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Signs and verifies requests with an app's signing secret
+#[derive(Clone)]
+pub struct Verifier {
+    secret: String,
+    max_age: u64,
+}
+
+impl Verifier {
+    /// Slack recommends rejecting anything more than five minutes old
+    const DEFAULT_MAX_AGE: u64 = 300;
+
+    pub fn new(signing_secret: &str) -> Verifier {
+        Verifier {
+            secret: signing_secret.to_string(),
+            max_age: Self::DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// How many seconds old a request can be, in either direction, before it's rejected
+    pub fn with_max_age(mut self, max_age: u64) -> Verifier {
+        self.max_age = max_age;
+        self
+    }
+
+    fn mac(&self, timestamp: &str, body: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(VERSION.as_bytes());
+        mac.update(b":");
+        mac.update(timestamp.as_bytes());
+        mac.update(b":");
+        mac.update(body);
+        mac
+    }
+
+    /// The `X-Slack-Signature` Slack would send with this body at this time
+    pub fn sign(&self, timestamp: u64, body: &[u8]) -> String {
+        let digest = self.mac(&timestamp.to_string(), body).finalize().into_bytes();
+        let hex = digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+
+        format!("{}={}", VERSION, hex)
+    }
+
+    /// Check a request's signature and timestamp, as taken from its headers
+    pub fn verify(&self, timestamp: &str, signature: &str, body: &[u8]) -> Result<(), VerifyError> {
+        let signed_at: u64 = timestamp.parse().map_err(|_| VerifyError::InvalidTimestamp)?;
+        if unix_now().abs_diff(signed_at) > self.max_age {
+            return Err(VerifyError::Expired);
+        }
+
+        let expected = signature.strip_prefix("v0=")
+            .and_then(decode_hex)
+            .ok_or(VerifyError::InvalidSignature)?;
+
+        // Compared in constant time, so the signature can't be guessed a byte at a time
+        self.mac(timestamp, body)
+            .verify_slice(&expected)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
+    /// Check a request using its `X-Slack-Request-Timestamp` and `X-Slack-Signature` headers
+    pub fn verify_headers(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), VerifyError> {
+        let header = |name: &'static str| headers.get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(VerifyError::MissingHeader(name));
+
+        self.verify(header(TIMESTAMP_HEADER)?, header(SIGNATURE_HEADER)?, body)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(hex.get(start..start + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from Slack's documentation on verifying requests
+    const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+    const TIMESTAMP: u64 = 1531420618;
+    const BODY: &str = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+    const SIGNATURE: &str = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(Verifier::new(SECRET).sign(TIMESTAMP, BODY.as_bytes()), SIGNATURE);
+    }
+
+    #[test]
+    fn test_verify() {
+        let verifier = Verifier::new(SECRET).with_max_age(u64::MAX);
+        let timestamp = TIMESTAMP.to_string();
+
+        assert_eq!(verifier.verify(&timestamp, SIGNATURE, BODY.as_bytes()), Ok(()));
+        assert_eq!(verifier.verify(&timestamp, SIGNATURE, b"text=tampered"), Err(VerifyError::InvalidSignature));
+        assert_eq!(verifier.verify(&timestamp, "v0=zz", BODY.as_bytes()), Err(VerifyError::InvalidSignature));
+        assert_eq!(verifier.verify("yesterday", SIGNATURE, BODY.as_bytes()), Err(VerifyError::InvalidTimestamp));
+        assert_eq!(Verifier::new("wrong").with_max_age(u64::MAX).verify(&timestamp, SIGNATURE, BODY.as_bytes()), Err(VerifyError::InvalidSignature));
+
+        // Long since expired under the default limit
+        assert_eq!(Verifier::new(SECRET).verify(&timestamp, SIGNATURE, BODY.as_bytes()), Err(VerifyError::Expired));
+        assert_eq!(Verifier::new(SECRET).verify_headers(&HeaderMap::new(), BODY.as_bytes()), Err(VerifyError::MissingHeader(TIMESTAMP_HEADER)));
+    }
+}
@@ -0,0 +1,364 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::oauth::{Installation, OAuthClient, RefreshedToken};
+use crate::transport::{HttpTransport, ReqwestTransport};
+use crate::types::*;
+use crate::SlackClient;
+
+/// Somewhere to keep the installations of an app distributed to multiple workspaces.
+///
+/// Installations are keyed by enterprise, team and installing user. Org-wide installs have no
+/// team, and match any team in their enterprise. Lookups without a user find the most recently
+/// saved installation for the workspace
+pub trait InstallationStore: Send + Sync {
+    /// Save an installation, replacing any previous one by the same user in the same workspace
+    fn save(&self, installation: Installation) -> Result<(), SlackError>;
+
+    fn find(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<Option<Installation>, SlackError>;
+
+    /// Delete the matching installations. Without a user, every installation in the workspace is
+    /// deleted, as happens when an app is uninstalled
+    fn delete(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<(), SlackError>;
+}
+
+impl Installation {
+    pub fn enterprise_id(&self) -> Option<&EnterpriseId> {
+        self.enterprise.as_ref().map(|enterprise| &enterprise.id)
+    }
+
+    pub fn team_id(&self) -> Option<&TeamId> {
+        self.team.as_ref().map(|team| &team.id)
+    }
+
+    fn matches(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> bool {
+        let workspace = if self.is_enterprise_install {
+            self.enterprise_id() == enterprise_id
+        } else {
+            self.enterprise_id() == enterprise_id && self.team_id() == team_id
+        };
+
+        workspace && user_id.is_none_or(|user_id| &self.user.id == user_id)
+    }
+
+    /// One of the installation's tokens, along with its refresh token and expiry if it rotates
+    fn token(&self, which: InstalledToken) -> Option<(&str, Option<&str>, Option<u64>)> {
+        match which {
+            InstalledToken::Bot => self.bot
+                .as_ref()
+                .map(|bot| (bot.token.as_str(), bot.refresh_token.as_deref(), bot.expires_at)),
+            InstalledToken::User => self.user.token
+                .as_deref()
+                .map(|token| (token, self.user.refresh_token.as_deref(), self.user.expires_at)),
+        }
+    }
+
+    fn apply_refresh(&mut self, which: InstalledToken, refreshed: &RefreshedToken) {
+        match which {
+            InstalledToken::Bot => {
+                if let Some(bot) = &mut self.bot {
+                    bot.token = refreshed.access_token.clone();
+                    bot.refresh_token = Some(refreshed.refresh_token.clone());
+                    bot.expires_at = Some(refreshed.expires_at);
+                }
+            }
+            InstalledToken::User => {
+                self.user.token = Some(refreshed.access_token.clone());
+                self.user.refresh_token = Some(refreshed.refresh_token.clone());
+                self.user.expires_at = Some(refreshed.expires_at);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum InstalledToken {
+    Bot,
+    User,
+}
+
+fn save_into(installations: &mut Vec<Installation>, installation: Installation) {
+    installations.retain(|other| {
+        !other.matches(installation.enterprise_id(), installation.team_id(), Some(&installation.user.id))
+    });
+    installations.push(installation);
+}
+
+fn find_in(installations: &[Installation], enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Option<Installation> {
+    installations.iter()
+        .rev()
+        .find(|installation| installation.matches(enterprise_id, team_id, user_id))
+        .cloned()
+}
+
+/// An installation store that only lasts as long as the process
+#[derive(Default)]
+pub struct MemoryInstallationStore {
+    installations: Mutex<Vec<Installation>>,
+}
+
+impl MemoryInstallationStore {
+    pub fn new() -> MemoryInstallationStore {
+        MemoryInstallationStore::default()
+    }
+}
+
+impl InstallationStore for MemoryInstallationStore {
+    fn save(&self, installation: Installation) -> Result<(), SlackError> {
+        save_into(&mut self.installations.lock().unwrap(), installation);
+        Ok(())
+    }
+
+    fn find(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<Option<Installation>, SlackError> {
+        Ok(find_in(&self.installations.lock().unwrap(), enterprise_id, team_id, user_id))
+    }
+
+    fn delete(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<(), SlackError> {
+        self.installations
+            .lock()
+            .unwrap()
+            .retain(|installation| !installation.matches(enterprise_id, team_id, user_id));
+        Ok(())
+    }
+}
+
+/// An installation store that keeps every installation in a single JSON file
+pub struct FileInstallationStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileInstallationStore {
+    pub fn new(path: impl Into<PathBuf>) -> FileInstallationStore {
+        FileInstallationStore {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<Installation>, SlackError> {
+        match fs::read(&self.path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write to a temporary file first, so a crash can't leave a half-written store behind. The
+    /// store holds tokens, so on unix the file is only readable by its owner
+    fn write(&self, installations: &[Installation]) -> Result<(), SlackError> {
+        let temp_path = self.path.with_extension("tmp");
+
+        // One left behind by a crash may have been created with looser permissions
+        match fs::remove_file(&temp_path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => (),
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        options.open(&temp_path)?.write_all(&serde_json::to_vec_pretty(installations)?)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl InstallationStore for FileInstallationStore {
+    fn save(&self, installation: Installation) -> Result<(), SlackError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut installations = self.read()?;
+        save_into(&mut installations, installation);
+        self.write(&installations)
+    }
+
+    fn find(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<Option<Installation>, SlackError> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(find_in(&self.read()?, enterprise_id, team_id, user_id))
+    }
+
+    fn delete(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>, user_id: Option<&UserId>) -> Result<(), SlackError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut installations = self.read()?;
+        installations.retain(|installation| !installation.matches(enterprise_id, team_id, user_id));
+        self.write(&installations)
+    }
+}
+
+/// Builds a [`SlackClient`] for whichever workspace an incoming request came from, using the
+/// tokens saved when the app was installed there
+pub struct ClientResolver {
+    store: Arc<dyn InstallationStore>,
+    oauth: Option<Arc<OAuthClient>>,
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl ClientResolver {
+    pub fn new(store: Arc<dyn InstallationStore>) -> ClientResolver {
+        ClientResolver {
+            store,
+            oauth: None,
+            transport: Arc::new(ReqwestTransport::new()),
+        }
+    }
+
+    /// Have the clients send requests with something other than `reqwest`
+    pub fn with_transport<T: HttpTransport + 'static>(mut self, transport: T) -> ClientResolver {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Refresh the tokens of installations that use token rotation, saving the new tokens back
+    /// to the store
+    pub fn with_token_rotation(mut self, oauth: Arc<OAuthClient>) -> ClientResolver {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    pub fn store(&self) -> &dyn InstallationStore {
+        &*self.store
+    }
+
+    /// Get a client for a workspace, or `None` if the app isn't installed there. The client holds
+    /// both the bot and user tokens, if the installation has both
+    pub fn client_for(&self, enterprise_id: Option<&EnterpriseId>, team_id: Option<&TeamId>) -> Result<Option<SlackClient>, SlackError> {
+        let installation = match self.store.find(enterprise_id, team_id, None)? {
+            Some(installation) => installation,
+            None => return Ok(None),
+        };
+
+        let mut client: Option<SlackClient> = None;
+        for which in [InstalledToken::Bot, InstalledToken::User] {
+            let (token, refresh_token, expires_at) = match installation.token(which) {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let token = match Token::parse(token) {
+                Some(token) => token,
+                None => continue,
+            };
+
+            let next = match client {
+                Some(client) => client.with_token(token),
+                None => SlackClient::with_shared_transport(token, self.transport.clone()),
+            };
+
+            client = Some(match (&self.oauth, refresh_token, expires_at) {
+                (Some(oauth), Some(refresh_token), Some(expires_at)) => {
+                    next.with_token_rotation(oauth.clone(), refresh_token, expires_at, self.save_refreshed(&installation, which))
+                }
+                _ => next,
+            });
+        }
+
+        Ok(client)
+    }
+
+    fn save_refreshed(&self, installation: &Installation, which: InstalledToken) -> impl Fn(&RefreshedToken) + Send + Sync + 'static {
+        let store = self.store.clone();
+        let enterprise_id = installation.enterprise_id().cloned();
+        let team_id = installation.team_id().cloned();
+        let user_id = installation.user.id.clone();
+
+        move |refreshed| {
+            // There's nowhere to report a failure from here. If the save is lost, the next
+            // client for this workspace will fail to refresh with the spent refresh token
+            if let Ok(Some(mut installation)) = store.find(enterprise_id.as_ref(), team_id.as_ref(), Some(&user_id)) {
+                installation.apply_refresh(which, refreshed);
+                let _ = store.save(installation);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::{EnterpriseInfo, InstallingUser, TeamInfo};
+    use crate::scopes::ScopeSet;
+
+    fn installation(enterprise: Option<&str>, team: Option<&str>, user: &str, token: &str) -> Installation {
+        Installation {
+            app_id: AppId::new("A1"),
+            team: team.map(|id| TeamInfo { id: TeamId::new(id), name: None }),
+            enterprise: enterprise.map(|id| EnterpriseInfo { id: EnterpriseId::new(id), name: None }),
+            is_enterprise_install: team.is_none(),
+            bot: None,
+            user: InstallingUser {
+                id: UserId::new(user),
+                token: Some(token.to_string()),
+                scopes: ScopeSet::new(),
+                refresh_token: None,
+                expires_at: None,
+            },
+            incoming_webhook: None,
+        }
+    }
+
+    fn check_store(store: &dyn InstallationStore) {
+        let team = TeamId::new("T1");
+        let enterprise = EnterpriseId::new("E1");
+
+        store.save(installation(None, Some("T1"), "U1", "xoxp-1")).unwrap();
+        store.save(installation(None, Some("T1"), "U2", "xoxp-2")).unwrap();
+        store.save(installation(None, Some("T1"), "U1", "xoxp-3")).unwrap();
+        store.save(installation(Some("E1"), None, "U4", "xoxp-4")).unwrap();
+
+        let latest = store.find(None, Some(&team), None).unwrap().unwrap();
+        assert_eq!(latest.user.token.as_deref(), Some("xoxp-3"));
+
+        let user = store.find(None, Some(&team), Some(&UserId::new("U2"))).unwrap().unwrap();
+        assert_eq!(user.user.token.as_deref(), Some("xoxp-2"));
+
+        let org = store.find(Some(&enterprise), Some(&TeamId::new("T9")), None).unwrap().unwrap();
+        assert_eq!(org.user.token.as_deref(), Some("xoxp-4"));
+
+        assert!(store.find(None, Some(&TeamId::new("T9")), None).unwrap().is_none());
+
+        store.delete(None, Some(&team), Some(&UserId::new("U1"))).unwrap();
+        let remaining = store.find(None, Some(&team), None).unwrap().unwrap();
+        assert_eq!(remaining.user.token.as_deref(), Some("xoxp-2"));
+
+        store.delete(None, Some(&team), None).unwrap();
+        assert!(store.find(None, Some(&team), None).unwrap().is_none());
+        assert!(store.find(Some(&enterprise), None, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        check_store(&MemoryInstallationStore::new());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("idle-installations-{}.json", std::process::id()));
+        check_store(&FileInstallationStore::new(&path));
+
+        let reopened = FileInstallationStore::new(&path);
+        assert!(reopened.find(Some(&EnterpriseId::new("E1")), None, None).unwrap().is_some());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_resolver() {
+        let store = Arc::new(MemoryInstallationStore::new());
+        store.save(installation(None, Some("T1"), "U1", "xoxp-1")).unwrap();
+
+        let resolver = ClientResolver::new(store);
+        assert!(resolver.client_for(None, Some(&TeamId::new("T1"))).unwrap().is_some());
+        assert!(resolver.client_for(None, Some(&TeamId::new("T2"))).unwrap().is_none());
+    }
+}
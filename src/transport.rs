@@ -0,0 +1,477 @@
+//! Keeping what's sent to Slack apart from how it's sent. Methods describe their calls as an
+//! [`ApiRequest`], which is encoded into plain bytes and headers as an [`HttpRequest`], and an
+//! [`HttpTransport`] does the actual I/O. [`ReqwestTransport`] is the default; swap in another
+//! with [`SlackClient::with_transport`](crate::SlackClient::with_transport) to use a different
+//! HTTP client, or a test double.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use http::{Method as HttpMethod, StatusCode};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::types::SlackError;
+
+/// How an API request's fields are put in the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// `application/x-www-form-urlencoded`, which every method accepts
+    Form,
+    /// `application/json`, which some methods accept, and which keeps nested fields intact
+    Json,
+    /// `multipart/form-data`, for uploading files
+    Multipart,
+}
+
+/// A file to upload as part of a multipart request
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    /// The name of the form field the file goes in
+    pub field: String,
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// A call to an API method, before it's been encoded for any particular HTTP client
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    /// The API method, such as `chat.postMessage`
+    pub method: String,
+    pub encoding: Encoding,
+    pub fields: BTreeMap<String, Value>,
+    pub files: Vec<FileUpload>,
+}
+
+impl ApiRequest {
+    pub fn new(method: &str) -> ApiRequest {
+        ApiRequest {
+            method: method.to_string(),
+            encoding: Encoding::Form,
+            fields: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> ApiRequest {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_field(mut self, name: &str, value: Value) -> ApiRequest {
+        self.fields.insert(name.to_string(), value);
+        self
+    }
+
+    /// Attach a file, which makes the request multipart
+    pub fn with_file(mut self, file: FileUpload) -> ApiRequest {
+        self.files.push(file);
+        self.encoding = Encoding::Multipart;
+        self
+    }
+
+    /// Each field as a string, as it's sent in a form: strings as they are, and anything else as
+    /// JSON, which is how Slack expects things like `blocks` in a form
+    pub fn text_fields(&self) -> impl Iterator<Item = (&str, String)> {
+        self.fields
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(name, value)| {
+                let text = match value {
+                    Value::String(str) => str.clone(),
+                    other => other.to_string(),
+                };
+                (name.as_str(), text)
+            })
+    }
+
+    /// Encode the request as an HTTP POST to the method under `api_url`. Fails if the token or a
+    /// file's content type has characters that can't be sent in a header
+    pub fn to_http(&self, api_url: &str, token: Option<&str>) -> Result<HttpRequest, SlackError> {
+        let (content_type, body) = match self.encoding {
+            Encoding::Form => {
+                let body = form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(self.text_fields())
+                    .finish();
+                (String::from("application/x-www-form-urlencoded"), body.into_bytes())
+            }
+            Encoding::Json => {
+                let fields = self.fields
+                    .iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .collect::<BTreeMap<_, _>>();
+                (String::from("application/json; charset=utf-8"), serde_json::to_vec(&fields).expect("JSON values serialize"))
+            }
+            Encoding::Multipart => self.multipart_body()?,
+        };
+
+        let mut request = HttpRequest::new(HttpMethod::POST, &(api_url.to_string() + &self.method))
+            .with_header(CONTENT_TYPE, &content_type)?;
+        if let Some(token) = token {
+            request = request.with_bearer(token)?;
+        }
+        request.body = body;
+        Ok(request)
+    }
+
+    /// Fails if a file's content type has characters that can't be sent in a header
+    fn multipart_body(&self) -> Result<(String, Vec<u8>), SlackError> {
+        let boundary = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(30)
+            .map(char::from)
+            .collect::<String>();
+
+        let mut body = Vec::new();
+        for (name, text) in self.text_fields() {
+            body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, disposition_param(name), text).as_bytes());
+        }
+        for file in &self.files {
+            // It's written out as a header, so it has to be a valid one
+            HeaderValue::from_str(&file.content_type)?;
+            body.extend_from_slice(format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                boundary, disposition_param(&file.field), disposition_param(&file.filename), file.content_type,
+            ).as_bytes());
+            body.extend_from_slice(&file.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        Ok((format!("multipart/form-data; boundary={}", boundary), body))
+    }
+}
+
+/// Percent-encode the characters that would end a quoted `Content-Disposition` parameter or its
+/// line, the way browsers do for multipart form data
+fn disposition_param(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// An HTTP request ready to send, with its body already encoded
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn new(method: HttpMethod, url: &str) -> HttpRequest {
+        HttpRequest {
+            method,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Add a header, failing if the value has characters headers can't hold, such as a newline
+    pub fn with_header(mut self, name: http::header::HeaderName, value: &str) -> Result<HttpRequest, SlackError> {
+        self.headers.insert(name, HeaderValue::from_str(value)?);
+        Ok(self)
+    }
+
+    pub fn with_bearer(self, token: &str) -> Result<HttpRequest, SlackError> {
+        self.with_header(AUTHORIZATION, &format!("Bearer {}", token))
+    }
+}
+
+/// An HTTP response, with the whole body read
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+/// A response body that's read a chunk at a time, so downloads needn't be held in memory
+pub trait BodyStream: Send {
+    /// The next chunk of the body, or `None` once it's all been read
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, SlackError>>;
+}
+
+/// An HTTP response whose body hasn't been read yet
+pub struct StreamingResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Box<dyn BodyStream>,
+}
+
+/// A body that was read in full anyway, handed over as a single chunk
+struct Buffered(Option<Vec<u8>>);
+
+impl BodyStream for Buffered {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, SlackError>> {
+        Box::pin(async move { Ok(self.0.take()) })
+    }
+}
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Sends HTTP requests. Implement this to make the crate use a different HTTP client
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>>;
+
+    /// Send a request without reading its body up front, for downloads. By default the body is
+    /// read in full by [`HttpTransport::send`], so override this if the client can stream
+    fn send_streaming(&self, request: HttpRequest) -> BoxFuture<'_, Result<StreamingResponse, SlackError>> {
+        Box::pin(async move {
+            let response = self.send(request).await?;
+
+            Ok(StreamingResponse {
+                status: response.status,
+                headers: response.headers,
+                body: Box::new(Buffered(Some(response.body))),
+            })
+        })
+    }
+}
+
+/// Sends requests with `reqwest`
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> ReqwestTransport {
+        ReqwestTransport::default()
+    }
+
+    /// Use an already configured `reqwest` client, such as one with a proxy or custom timeouts
+    pub fn with_client(client: reqwest::Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>> {
+        Box::pin(async move {
+            let response = self.client
+                .request(request.method, &request.url)
+                .headers(request.headers)
+                .body(request.body)
+                .send()
+                .await?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await?.to_vec();
+
+            Ok(HttpResponse { status, headers, body })
+        })
+    }
+
+    fn send_streaming(&self, request: HttpRequest) -> BoxFuture<'_, Result<StreamingResponse, SlackError>> {
+        Box::pin(async move {
+            let response = self.client
+                .request(request.method, &request.url)
+                .headers(request.headers)
+                .body(request.body)
+                .send()
+                .await?;
+
+            Ok(StreamingResponse {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: Box::new(ReqwestBody(response)),
+            })
+        })
+    }
+}
+
+struct ReqwestBody(reqwest::Response);
+
+impl BodyStream for ReqwestBody {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, SlackError>> {
+        Box::pin(async move {
+            Ok(self.0.chunk().await?.map(|chunk| chunk.to_vec()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::Any;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use serde_json::json;
+    use crate::methods::{Method, UnknownInputs};
+    use crate::types::{ChannelId, Token};
+    use crate::{Method, SlackClient};
+
+    fn request() -> ApiRequest {
+        ApiRequest::new("chat.postMessage")
+            .with_field("channel", json!("C12345678"))
+            .with_field("text", json!("Hello & welcome"))
+            .with_field("unfurl_links", json!(false))
+            .with_field("blocks", json!([{ "type": "divider" }]))
+    }
+
+    fn header<'a>(request: &'a HttpRequest, name: &str) -> &'a str {
+        request.headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn test_encodings() {
+        let form = request().to_http("https://slack.com/api/", Some("xoxb-1234")).unwrap();
+        assert_eq!(form.url, "https://slack.com/api/chat.postMessage");
+        assert_eq!(header(&form, "authorization"), "Bearer xoxb-1234");
+        assert_eq!(header(&form, "content-type"), "application/x-www-form-urlencoded");
+        assert_eq!(
+            String::from_utf8(form.body).unwrap(),
+            "blocks=%5B%7B%22type%22%3A%22divider%22%7D%5D&channel=C12345678&text=Hello+%26+welcome&unfurl_links=false",
+        );
+
+        let json = request().with_encoding(Encoding::Json).to_http("https://slack.com/api/", None).unwrap();
+        assert!(json.headers.get("authorization").is_none());
+        assert_eq!(header(&json, "content-type"), "application/json; charset=utf-8");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&json.body).unwrap(),
+            json!({ "channel": "C12345678", "text": "Hello & welcome", "unfurl_links": false, "blocks": [{ "type": "divider" }] }),
+        );
+
+        let multipart = ApiRequest::new("files.upload")
+            .with_field("channels", json!("C12345678"))
+            .with_file(FileUpload {
+                field: String::from("file"),
+                filename: String::from("notes.txt"),
+                content_type: String::from("text/plain"),
+                data: b"Some notes".to_vec(),
+            })
+            .to_http("https://slack.com/api/", None)
+            .unwrap();
+        let boundary = header(&multipart, "content-type").strip_prefix("multipart/form-data; boundary=").unwrap();
+        let body = String::from_utf8(multipart.body.clone()).unwrap();
+        assert!(body.starts_with(&format!("--{}\r\nContent-Disposition: form-data; name=\"channels\"\r\n\r\nC12345678\r\n", boundary)));
+        assert!(body.contains("name=\"file\"; filename=\"notes.txt\"\r\nContent-Type: text/plain\r\n\r\nSome notes\r\n"));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+    }
+
+    #[test]
+    fn test_multipart_escaping() {
+        let upload = |filename: &str, content_type: &str| ApiRequest::new("files.upload")
+            .with_file(FileUpload {
+                field: String::from("file"),
+                filename: filename.to_string(),
+                content_type: content_type.to_string(),
+                data: b"<script></script>".to_vec(),
+            })
+            .to_http("https://slack.com/api/", None);
+
+        let multipart = upload("notes\".txt\r\nContent-Type: text/html", "text/plain").unwrap();
+        let body = String::from_utf8(multipart.body).unwrap();
+        assert!(body.contains("name=\"file\"; filename=\"notes%22.txt%0D%0AContent-Type: text/html\"\r\nContent-Type: text/plain\r\n\r\n"));
+        assert!(!body.contains("\r\nContent-Type: text/html"));
+
+        assert!(matches!(upload("notes.txt", "text/plain\r\nX-Injected: true"), Err(SlackError::InvalidHeader(_))));
+    }
+
+    #[test]
+    fn test_write_out() {
+        let channel = ChannelId::new("C12345678");
+        let text = String::from("Hello");
+        let request = <Method!["chat.postMessage"]>::write_out(HashMap::from([
+            (String::from("channel"), &channel as &dyn Any),
+            (String::from("text"), &text as &dyn Any),
+        ]), UnknownInputs::Reject).unwrap();
+
+        assert_eq!(request.method, "chat.postMessage");
+        assert_eq!(request.encoding, Encoding::Json);
+        assert_eq!(request.fields, BTreeMap::from([
+            (String::from("channel"), json!("C12345678")),
+            (String::from("text"), json!("Hello")),
+        ]));
+    }
+
+    /// Answers every request itself, remembering what it was asked
+    #[derive(Default)]
+    struct Recorder {
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl HttpTransport for Arc<Recorder> {
+        fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>> {
+            self.requests.lock().unwrap().push(request);
+
+            Box::pin(async {
+                Ok(HttpResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: br#"{"ok": true, "channels": [], "response_metadata": {"next_cursor": ""}}"#.to_vec(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let recorder = Arc::new(Recorder::default());
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_transport(recorder.clone());
+
+        let response = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await.unwrap();
+        assert!(response.data().0.is_empty());
+
+        let requests = recorder.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, "https://slack.com/api/conversations.list");
+        assert_eq!(header(&requests[0], "authorization"), "Bearer xoxb-1234");
+    }
+
+    /// Serves downloads a chunk at a time, and nothing else
+    struct Chunked {
+        chunks: Vec<&'static [u8]>,
+    }
+
+    struct Chunks(Vec<&'static [u8]>);
+
+    impl BodyStream for Chunks {
+        fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, SlackError>> {
+            let chunk = if self.0.is_empty() { None } else { Some(self.0.remove(0).to_vec()) };
+            Box::pin(async { Ok(chunk) })
+        }
+    }
+
+    impl HttpTransport for Chunked {
+        fn send(&self, _: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, SlackError>> {
+            panic!("Downloads should be streamed")
+        }
+
+        fn send_streaming(&self, _: HttpRequest) -> BoxFuture<'_, Result<StreamingResponse, SlackError>> {
+            let body = Box::new(Chunks(self.chunks.clone()));
+            Box::pin(async {
+                Ok(StreamingResponse { status: StatusCode::OK, headers: HeaderMap::new(), body })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_download() {
+        let client = SlackClient::new(Token::parse("xoxb-1234").unwrap())
+            .with_transport(Chunked { chunks: vec![b"Some ", b"notes", b""] });
+
+        let mut notes = Vec::new();
+        let written = client.download_url("https://files.slack.com/files-pri/T1-F1/notes.txt", &mut notes).await.unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(notes, b"Some notes");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_token_header() {
+        let recorder = Arc::new(Recorder::default());
+        let client = SlackClient::new(Token::parse("xoxb-1234\n").unwrap())
+            .with_transport(recorder.clone());
+
+        let result = client.make_request::<Method!["conversations.list"]>(HashMap::new()).await;
+        assert!(matches!(result, Err(SlackError::InvalidHeader(_))));
+        assert!(recorder.requests.lock().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,10 @@
-use serde::{Serialize, Deserialize, Deserializer};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use serde::de::Error as SerdeError;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::scopes::{Scope, TokenType};
 
 fn bool_false<'de, D>(de: D) -> Result<bool, D::Error>
 where
@@ -9,16 +13,101 @@ where
     if bool::deserialize(de)? {
         Err(D::Error::custom("Expected boolean false value"))
     } else {
+        Ok(false)
+    }
+}
+
+fn bool_true<'de, D>(de: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>
+{
+    if bool::deserialize(de)? {
         Ok(true)
+    } else {
+        Err(D::Error::custom("Expected boolean true value"))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+thread_local! {
+    /// The names of unknown fields seen while parsing in strict mode, or `None` outside it
+    static UNKNOWN_FIELDS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Run `parse`, also returning the names of any fields it came across that the models don't
+/// know about, at any depth
+pub(crate) fn collect_unknown_fields<R>(parse: impl FnOnce() -> R) -> (R, Vec<String>) {
+    UNKNOWN_FIELDS.with(|unknown| *unknown.borrow_mut() = Some(Vec::new()));
+    let result = parse();
+    let unknown = UNKNOWN_FIELDS.with(|unknown| unknown.borrow_mut().take());
+
+    (result, unknown.unwrap_or_default())
+}
+
+/// Keep the fields a model doesn't know about, since Slack adds new ones all the time
+fn extra_fields<'de, D>(de: D) -> Result<Map<String, Value>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let extra = Map::deserialize(de)?;
+
+    UNKNOWN_FIELDS.with(|unknown| {
+        if let Some(unknown) = unknown.borrow_mut().as_mut() {
+            unknown.extend(extra.keys().cloned());
+        }
+    });
+
+    Ok(extra)
+}
+
+/// Some endpoints return counts as strings, others as numbers
+fn u64_or_string<'de, D>(de: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Count {
+        Num(u64),
+        Str(String),
+    }
+
+    match Option::<Count>::deserialize(de)? {
+        Some(Count::Num(num)) => Ok(Some(num)),
+        Some(Count::Str(str)) => str.parse()
+            .map(Some)
+            .map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum Error {
     AccountInactive,
+    BadClientSecret,
+    BadRedirectUri,
+    AlreadyReacted,
+    CantDeleteMessage,
+    CantUpdateMessage,
+    ChannelNotFound,
+    InvalidAuth,
+    InvalidClientId,
+    InvalidCode,
+    InvalidRefreshToken,
+    IsArchived,
+    MessageNotFound,
+    MissingScope,
+    NameTaken,
+    NoReaction,
+    NoText,
     NotAuthed,
     NotAllowedTokenType,
+    Ratelimited,
+    ThreadNotFound,
+    TokenExpired,
+    UserNotFound,
+    /// An error this crate doesn't know about yet
+    Unknown(String),
 }
 
 impl Error {
@@ -27,9 +116,29 @@ impl Error {
             .map(|item| {
                 match item {
                     "account_inactive" => Error::AccountInactive,
+                    "bad_client_secret" => Error::BadClientSecret,
+                    "bad_redirect_uri" => Error::BadRedirectUri,
+                    "already_reacted" => Error::AlreadyReacted,
+                    "cant_delete_message" => Error::CantDeleteMessage,
+                    "cant_update_message" => Error::CantUpdateMessage,
+                    "channel_not_found" => Error::ChannelNotFound,
+                    "invalid_auth" => Error::InvalidAuth,
+                    "invalid_client_id" => Error::InvalidClientId,
+                    "invalid_code" => Error::InvalidCode,
+                    "invalid_refresh_token" => Error::InvalidRefreshToken,
+                    "is_archived" => Error::IsArchived,
+                    "message_not_found" => Error::MessageNotFound,
+                    "missing_scope" => Error::MissingScope,
+                    "name_taken" => Error::NameTaken,
+                    "no_reaction" => Error::NoReaction,
+                    "no_text" => Error::NoText,
                     "not_authed" => Error::NotAuthed,
                     "not_allowed_token_type" => Error::NotAllowedTokenType,
-                    _ => panic!("Unknown Error: {}", item)
+                    "ratelimited" => Error::Ratelimited,
+                    "thread_not_found" => Error::ThreadNotFound,
+                    "token_expired" => Error::TokenExpired,
+                    "user_not_found" => Error::UserNotFound,
+                    _ => Error::Unknown(item.to_string()),
                 }
             })
             .collect()
@@ -37,22 +146,55 @@ impl Error {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq)]
 #[non_exhaustive]
-pub enum Warning {}
+pub enum Warning {
+    MethodDeprecated,
+    MissingCharset,
+    SuperfluousCharset,
+    /// A warning this crate doesn't know about yet
+    Unknown(String),
+}
 
 impl Warning {
     pub(crate) fn from_str(str: String) -> Vec<Warning> {
         str.split(",")
-            .map(|item| {
-                match item {
-                    _ => panic!("Unknown Warning: {}", item)
-                }
-            })
+            .map(Warning::from_name)
             .collect()
     }
+
+    fn from_name(name: &str) -> Warning {
+        match name {
+            "method_deprecated" => Warning::MethodDeprecated,
+            "missing_charset" => Warning::MissingCharset,
+            "superfluous_charset" => Warning::SuperfluousCharset,
+            _ => Warning::Unknown(name.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Warning::MethodDeprecated => "method_deprecated",
+            Warning::MissingCharset => "missing_charset",
+            Warning::SuperfluousCharset => "superfluous_charset",
+            Warning::Unknown(name) => name,
+        }
+    }
 }
 
+impl Serialize for Warning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Warning {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Warning, D::Error> {
+        Ok(Warning::from_name(&String::deserialize(de)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RateLimit {
     Tier1,
     Tier2,
@@ -64,12 +206,12 @@ pub enum RateLimit {
     Events,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppToken(String);
 
 impl AppToken {
     pub fn new(str: &str) -> Option<AppToken> {
-        if str.starts_with("xapp") {
+        if str.starts_with("xapp-") {
             Some(AppToken(str.to_string()))
         } else {
             None
@@ -77,60 +219,247 @@ impl AppToken {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BotToken(String);
 
 impl BotToken {
-    pub fn new(str: &str) -> Option<AppToken> {
-        if str.starts_with("xoxb") {
-            Some(AppToken(str.to_string()))
+    pub fn new(str: &str) -> Option<BotToken> {
+        if str.starts_with("xoxb-") {
+            Some(BotToken(str.to_string()))
         } else {
             None
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UserToken(String);
 
 impl UserToken {
-    pub fn new(str: &str) -> Option<AppToken> {
-        if str.starts_with("xoxp") {
-            Some(AppToken(str.to_string()))
+    pub fn new(str: &str) -> Option<UserToken> {
+        if str.starts_with("xoxp-") {
+            Some(UserToken(str.to_string()))
         } else {
             None
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AppId(String);
+/// An app configuration token, used with the `apps.manifest.*` methods
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigToken(String);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BotId(String);
+impl ConfigToken {
+    pub fn new(str: &str) -> Option<ConfigToken> {
+        if str.starts_with("xoxe.") {
+            Some(ConfigToken(str.to_string()))
+        } else {
+            None
+        }
+    }
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ChannelId(String);
+/// A refresh token, from either token rotation or app configuration tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshToken(String);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CommentId(String);
+impl RefreshToken {
+    pub fn new(str: &str) -> Option<RefreshToken> {
+        if str.starts_with("xoxe-") {
+            Some(RefreshToken(str.to_string()))
+        } else {
+            None
+        }
+    }
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct EnterpriseId(String);
+/// Any kind of token Slack issues, told apart by its prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    App(AppToken),
+    Bot(BotToken),
+    User(UserToken),
+    Config(ConfigToken),
+    Refresh(RefreshToken),
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TeamId(String);
+impl Token {
+    pub fn parse(str: &str) -> Option<Token> {
+        AppToken::new(str).map(Token::App)
+            .or_else(|| BotToken::new(str).map(Token::Bot))
+            .or_else(|| UserToken::new(str).map(Token::User))
+            .or_else(|| ConfigToken::new(str).map(Token::Config))
+            .or_else(|| RefreshToken::new(str).map(Token::Refresh))
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UserId(String);
+    pub fn as_str(&self) -> &str {
+        match self {
+            Token::App(AppToken(str))
+            | Token::Bot(BotToken(str))
+            | Token::User(UserToken(str))
+            | Token::Config(ConfigToken(str))
+            | Token::Refresh(RefreshToken(str)) => str,
+        }
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct WorkspaceId(String);
+    /// The kind of token this is for the purposes of scopes. Config and refresh tokens can't be
+    /// granted scopes, so have none
+    pub fn token_type(&self) -> Option<TokenType> {
+        match self {
+            Token::App(_) => Some(TokenType::App),
+            Token::Bot(_) => Some(TokenType::Bot),
+            Token::User(_) => Some(TokenType::User),
+            Token::Config(_) | Token::Refresh(_) => None,
+        }
+    }
+
+    /// Check this token can be used with at least one of a method's `scopes`, which are
+    /// alternatives. Returns the first scope if it can't use any of them
+    pub(crate) fn check_scopes(&self, scopes: &[Scope]) -> Result<(), &'static str> {
+        let token_type = self.token_type();
+        let allowed = |scope: &Scope| token_type.is_none_or(|ty| scope.token_types().contains(&ty));
+
+        match scopes.first() {
+            Some(first) if !scopes.iter().any(allowed) => Err(first.name()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<AppToken> for Token {
+    fn from(token: AppToken) -> Token {
+        Token::App(token)
+    }
+}
+
+impl From<BotToken> for Token {
+    fn from(token: BotToken) -> Token {
+        Token::Bot(token)
+    }
+}
+
+impl From<UserToken> for Token {
+    fn from(token: UserToken) -> Token {
+        Token::User(token)
+    }
+}
+
+macro_rules! id_type {
+    ($($name:ident),* $(,)?) => {
+        $(
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: &str) -> $name {
+                $name(id.to_string())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+        )*
+    };
+}
+
+id_type! {
+    AppId,
+    BotId,
+    ChannelId,
+    CommentId,
+    EnterpriseId,
+    FileId,
+    TeamId,
+    UserId,
+    UsergroupId,
+    WorkspaceId,
+}
+
+/// What was wrong with the inputs to a method, caught before making the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    /// A required input was left out
+    Missing(&'static str),
+    /// None of a group of inputs was given, when one is needed
+    MissingOneOf(&'static [&'static str]),
+    /// More than one of a group of mutually exclusive inputs was given
+    Conflicting(Vec<&'static str>),
+    /// An input was given as a different type to the one the method takes
+    WrongType {
+        name: &'static str,
+        expected: &'static str,
+    },
+    /// An input couldn't be serialized
+    Unserializable(&'static str),
+    /// An input the method doesn't define was given
+    Unknown(String),
+    /// An unknown input was to be forwarded as is, but it isn't of a type that can be
+    NotForwardable(String),
+}
 
 #[derive(Debug)]
 pub enum SlackError {
     ReqwestError(reqwest::Error),
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
     ApiError(Vec<Error>),
+    /// A file was asked to be downloaded, but it has no private URL to fetch it from
+    NoDownloadUrl,
+    /// A download URL isn't on Slack's file host or the API's host, so the token wasn't sent to it
+    UntrustedUrl(String),
+    /// The client's token can't be used with a scope the method requires, so the request wasn't
+    /// sent
+    NotAllowedTokenType {
+        method: &'static str,
+        scope: &'static str,
+    },
+    /// The token hasn't been granted any of the method's scopes, so the request wasn't sent.
+    /// `missing` lists the ones it could be granted
+    MissingScopes {
+        method: &'static str,
+        missing: Vec<Scope>,
+    },
+    /// A request asked for a kind of token the client doesn't hold
+    MissingToken(TokenType),
+    /// Slack gave us a token we don't recognize the kind of
+    InvalidToken,
+    /// Slack is rate limiting this method, and asked us to wait `retry_after` seconds
+    RateLimited {
+        retry_after: Option<u64>,
+    },
+    /// The inputs to a method don't fit its definition, so the request wasn't sent
+    InvalidInput {
+        method: &'static str,
+        reason: InputError,
+    },
+    /// A header couldn't be sent, usually because the token has a stray character like a newline
+    InvalidHeader(http::header::InvalidHeaderValue),
+    /// A custom transport failed to send a request
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// A URL that isn't an API method, such as a file download, answered with an error status
+    HttpStatus(u16),
+    /// Replaying a cassette that has no unplayed recording of a request to this method with
+    /// these fields
+    NotRecorded(String),
+    /// A response didn't match the model of one of the method's outputs, either because it was
+    /// missing or because a value inside it had the wrong type. `source` says where
+    Deserialize {
+        method: &'static str,
+        field: &'static str,
+        source: serde_path_to_error::Error<serde_json::Error>,
+    },
+    /// In strict parsing mode, a response had fields the models don't know about
+    UnknownFields {
+        method: &'static str,
+        fields: Vec<String>,
+    },
 }
 
 impl From<reqwest::Error> for SlackError {
@@ -139,15 +468,54 @@ impl From<reqwest::Error> for SlackError {
     }
 }
 
+impl From<serde_json::Error> for SlackError {
+    fn from(err: serde_json::Error) -> SlackError {
+        SlackError::JsonError(err)
+    }
+}
+
+impl From<http::header::InvalidHeaderValue> for SlackError {
+    fn from(err: http::header::InvalidHeaderValue) -> SlackError {
+        SlackError::InvalidHeader(err)
+    }
+}
+
+impl From<std::io::Error> for SlackError {
+    fn from(err: std::io::Error) -> SlackError {
+        SlackError::IoError(err)
+    }
+}
+
 #[derive(Debug)]
 pub struct SlackResponse<T> {
     pub(crate) data: T,
     pub(crate) warnings: Option<Vec<Warning>>,
+    pub(crate) extra: Map<String, Value>,
+}
+
+impl<T> SlackResponse<T> {
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    pub fn into_data(self) -> T {
+        self.data
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        self.warnings.as_deref().unwrap_or(&[])
+    }
+
+    /// Top-level fields of the response that the method doesn't know about
+    pub fn extra(&self) -> &Map<String, Value> {
+        &self.extra
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct RawResponse {
     pub(crate) ok: bool,
+    #[serde(rename = "warning")]
     pub(crate) warnings: Option<String>,
     pub(crate) error: Option<String>,
     #[serde(flatten)]
@@ -155,140 +523,306 @@ pub struct RawResponse {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, untagged)]
+#[serde(untagged)]
 pub enum ResponseMeta {
     PagingWarning {
         next_cursor: String,
         messages: Vec<String>,
         warnings: Vec<Warning>,
+        #[serde(flatten, deserialize_with = "extra_fields")]
+        extra: Map<String, Value>,
     },
     Paging {
-        next_cursor: String
+        next_cursor: String,
+        #[serde(flatten, deserialize_with = "extra_fields")]
+        extra: Map<String, Value>,
     },
     Warning {
         messages: Vec<String>,
         warnings: Vec<Warning>,
+        #[serde(flatten, deserialize_with = "extra_fields")]
+        extra: Map<String, Value>,
     },
 }
 
+impl ResponseMeta {
+    /// The cursor to pass to get the next page of results, if there is one
+    pub fn next_cursor(&self) -> Option<&str> {
+        match self {
+            ResponseMeta::PagingWarning { next_cursor, .. } | ResponseMeta::Paging { next_cursor, .. } => {
+                Some(next_cursor.as_str()).filter(|cursor| !cursor.is_empty())
+            }
+            ResponseMeta::Warning { .. } => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Topic {
-    value: String,
-    creator: String,
-    last_set: u64,
+    pub value: String,
+    pub creator: String,
+    pub last_set: u64,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Purpose {
-    value: String,
-    creator: String,
-    last_set: u64,
+    pub value: String,
+    pub creator: String,
+    pub last_set: u64,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A message timestamp, which Slack uses as the ID of a message within its channel
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Timestamp(String);
 
+impl Timestamp {
+    pub fn new(ts: &str) -> Timestamp {
+        Timestamp(ts.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Page-number based paging, as returned by `files.list` and the `search.*` family
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Paging {
+    pub count: u64,
+    pub total: u64,
+    pub page: u64,
+    pub pages: u64,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+impl Paging {
+    /// The page number to request next, if this isn't the last page
+    pub fn next_page(&self) -> Option<u64> {
+        if self.page < self.pages {
+            Some(self.page + 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// The newer style of page-number paging some `search.*` results include alongside [`Paging`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pagination {
+    pub total_count: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub page_count: u64,
+    pub first: u64,
+    pub last: u64,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DisplayCounts {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Share {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Attachment {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Block {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BotProfile {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Comment {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct File {
-    // TODO
+    pub id: FileId,
+    pub created: Option<u64>,
+    pub timestamp: Option<u64>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub mimetype: Option<String>,
+    pub filetype: Option<String>,
+    pub pretty_type: Option<String>,
+    pub user: Option<UserId>,
+    pub user_team: Option<TeamId>,
+    pub source_team: Option<TeamId>,
+    pub mode: Option<String>,
+    pub size: Option<u64>,
+
+    pub editable: Option<bool>,
+    pub is_external: Option<bool>,
+    pub is_public: Option<bool>,
+    pub is_starred: Option<bool>,
+    pub display_as_bot: Option<bool>,
+    pub has_rich_preview: Option<bool>,
+    pub public_url_shared: Option<bool>,
+
+    pub external_type: Option<String>,
+    pub external_id: Option<String>,
+    pub external_url: Option<String>, // TODO: Actually URI
+    pub username: Option<String>,
+
+    pub url_private: Option<String>, // TODO: Actually URI
+    pub url_private_download: Option<String>, // TODO: Actually URI
+    pub permalink: Option<String>, // TODO: Actually URI
+    pub permalink_public: Option<String>, // TODO: Actually URI
+    pub edit_link: Option<String>, // TODO: Actually URI
+
+    pub thumb_64: Option<String>,
+    pub thumb_80: Option<String>,
+    pub thumb_160: Option<String>,
+    pub thumb_360: Option<String>,
+    pub thumb_480: Option<String>,
+    pub thumb_720: Option<String>,
+    pub thumb_960: Option<String>,
+    pub thumb_1024: Option<String>,
+    pub thumb_tiny: Option<String>,
+    pub original_w: Option<u64>,
+    pub original_h: Option<u64>,
+
+    pub preview: Option<String>,
+    pub preview_highlight: Option<String>,
+    pub lines: Option<u64>,
+    pub lines_more: Option<u64>,
+
+    pub channels: Option<Vec<ChannelId>>,
+    pub groups: Option<Vec<ChannelId>>,
+    pub ims: Option<Vec<ChannelId>>,
+    pub pinned_to: Option<Vec<ChannelId>>,
+    pub comments_count: Option<u64>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
+impl File {
+    /// The URL to download the raw file contents from, preferring the explicit download link
+    pub fn download_url(&self) -> Option<&str> {
+        self.url_private_download
+            .as_deref()
+            .or(self.url_private.as_deref())
+    }
+}
+
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Icon {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Reaction {
-    // TODO
+    pub name: String,
+    pub count: u64,
+    pub users: Vec<UserId>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
+/// Not modelled yet, so every field is kept in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserProfileShort {
-    // TODO
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct Message {
-    attachments: Option<Vec<Attachment>>,
-    blocks: Option<Block>,
-    bot_id: Option<BotId>,
-    bot_profile: Option<BotProfile>,
-    client_msg_id: Option<String>,
-    comment: Option<Comment>,
-    display_as_bot: Option<bool>,
-    file: Option<File>,
-    files: Option<Vec<File>>,
-    icons: Option<Icon>,
-    inviter: Option<UserId>,
-
-    is_delayed_message: Option<bool>,
-    is_intro: Option<bool>,
-    is_starred: Option<bool>,
-
-    last_read: Option<Timestamp>,
-    latest_reply: Option<Timestamp>,
-    name: Option<String>,
-    old_name: Option<String>,
-    parent_user_id: Option<UserId>,
-    permalink: Option<String>, // TODO: Actually URI
-    pinned_to: Option<Vec<ChannelId>>,
-    purpose: Option<String>,
-    reactions: Option<Vec<Reaction>>,
-    reply_count: Option<u64>,
-    reply_users: Option<Vec<UserId>>,
-    reply_users_count: Option<u64>,
-    source_team: Option<WorkspaceId>,
-    subscribed: Option<bool>,
-    subtype: Option<String>,
-    team: Option<WorkspaceId>,
-    text: String,
-    thread_ts: Option<Timestamp>,
-    topic: Option<String>,
-    ts: Timestamp,
+    pub attachments: Option<Vec<Attachment>>,
+    pub blocks: Option<Vec<Block>>,
+    pub bot_id: Option<BotId>,
+    pub bot_profile: Option<BotProfile>,
+    pub client_msg_id: Option<String>,
+    pub comment: Option<Comment>,
+    pub display_as_bot: Option<bool>,
+    pub file: Option<File>,
+    pub files: Option<Vec<File>>,
+    pub icons: Option<Icon>,
+    pub inviter: Option<UserId>,
+
+    pub is_delayed_message: Option<bool>,
+    pub is_intro: Option<bool>,
+    pub is_starred: Option<bool>,
+
+    pub last_read: Option<Timestamp>,
+    pub latest_reply: Option<Timestamp>,
+    pub name: Option<String>,
+    pub old_name: Option<String>,
+    pub parent_user_id: Option<UserId>,
+    pub permalink: Option<String>, // TODO: Actually URI
+    pub pinned_to: Option<Vec<ChannelId>>,
+    pub purpose: Option<String>,
+    pub reactions: Option<Vec<Reaction>>,
+    pub reply_count: Option<u64>,
+    pub reply_users: Option<Vec<UserId>>,
+    pub reply_users_count: Option<u64>,
+    pub source_team: Option<WorkspaceId>,
+    pub subscribed: Option<bool>,
+    pub subtype: Option<String>,
+    pub team: Option<WorkspaceId>,
+    pub text: String,
+    pub thread_ts: Option<Timestamp>,
+    pub topic: Option<String>,
+    pub ts: Timestamp,
     #[serde(rename = "type")]
-    ty: String,
-    unread_count: Option<u64>,
-    upload: Option<bool>,
-    user: Option<UserId>,
-    user_profile: Option<UserProfileShort>,
-    user_team: Option<WorkspaceId>,
-    username: Option<String>,
+    pub ty: String,
+    pub unread_count: Option<u64>,
+    pub upload: Option<bool>,
+    pub user: Option<UserId>,
+    pub user_profile: Option<UserProfileShort>,
+    pub user_team: Option<WorkspaceId>,
+    pub username: Option<String>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct Channel {
     accepted_user: UserId,
     created: u64,
@@ -324,85 +858,385 @@ pub struct Channel {
     unlinked: u64,
     unread_count: u64,
     unread_count_display: u64,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Conversation {
-    Base(ConversationBase),
+    Base(Box<ConversationBase>),
     Mpim(ConversationMpim),
     Im(ConversationIm),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct ConversationBase {
-    accepted_user: Option<UserId>,
-    connected_team_ids: Option<Vec<WorkspaceId>>,
-    conversation_host_id: Option<WorkspaceId>,
-    created: u64,
-    creator: UserId,
-    display_counts: Option<DisplayCounts>,
-    enterprise_id: Option<EnterpriseId>,
-    has_pins: Option<bool>,
-    id: ChannelId,
-    internal_team_ids: Option<Vec<TeamId>>,
+    pub accepted_user: Option<UserId>,
+    pub connected_team_ids: Option<Vec<WorkspaceId>>,
+    pub conversation_host_id: Option<WorkspaceId>,
+    pub created: u64,
+    pub creator: UserId,
+    pub display_counts: Option<DisplayCounts>,
+    pub enterprise_id: Option<EnterpriseId>,
+    pub has_pins: Option<bool>,
+    pub id: ChannelId,
+    pub internal_team_ids: Option<Vec<TeamId>>,
 
-    is_archived: bool,
-    is_channel: bool,
-    is_ext_shared: Option<bool>,
-    is_frozen: Option<bool>,
-    is_general: bool,
-    is_global_shared: Option<bool>,
-    is_group: bool,
-    is_im: bool,
-    is_member: Option<bool>,
-    is_moved: Option<u64>,
+    pub is_archived: bool,
+    pub is_channel: bool,
+    pub is_ext_shared: Option<bool>,
+    pub is_frozen: Option<bool>,
+    pub is_general: bool,
+    pub is_global_shared: Option<bool>,
+    pub is_group: bool,
+    pub is_im: bool,
+    pub is_member: Option<bool>,
+    pub is_moved: Option<u64>,
     #[serde(deserialize_with = "bool_false")]
-    is_mpim: bool,
-    is_non_threadable: Option<bool>,
-    is_open: Option<bool>,
-    is_org_default: Option<bool>,
-    is_org_mandatory: Option<bool>,
-    is_org_shared: bool,
-    is_pending_ext_shared: Option<bool>,
-    is_private: bool,
-    is_read_only: Option<bool>,
-    is_shared: bool,
-    is_starred: Option<bool>,
-    is_thread_only: Option<bool>,
+    pub is_mpim: bool,
+    pub is_non_threadable: Option<bool>,
+    pub is_open: Option<bool>,
+    pub is_org_default: Option<bool>,
+    pub is_org_mandatory: Option<bool>,
+    pub is_org_shared: bool,
+    pub is_pending_ext_shared: Option<bool>,
+    pub is_private: bool,
+    pub is_read_only: Option<bool>,
+    pub is_shared: bool,
+    pub is_starred: Option<bool>,
+    pub is_thread_only: Option<bool>,
 
-    last_read: Option<Timestamp>,
-    latest: Option<Message>,
-    members: Option<Vec<UserId>>,
-    name: String,
-    name_normalized: String,
-    num_members: Option<u64>,
-    parent_conversation: Option<ChannelId>,
-    pending_connected_team_ids: Option<Vec<TeamId>>,
-    pending_shared: Option<Vec<TeamId>>,
-    pin_count: Option<u64>,
-    previous_names: Option<Vec<String>>,
-    priority: Option<f64>,
-    purpose: Purpose,
-    shared_team_ids: Option<Vec<TeamId>>,
-    shares: Option<Vec<Share>>,
-    timezone_count: Option<u64>,
-    topic: Topic,
-    unlinked: Option<u64>,
-    unread_count: Option<u64>,
-    unread_count_display: Option<u64>,
-    use_case: Option<String>,
-    user: Option<UserId>,
-    version: Option<u64>,
+    pub last_read: Option<Timestamp>,
+    pub latest: Option<Message>,
+    pub members: Option<Vec<UserId>>,
+    pub name: String,
+    pub name_normalized: String,
+    pub num_members: Option<u64>,
+    pub parent_conversation: Option<ChannelId>,
+    pub pending_connected_team_ids: Option<Vec<TeamId>>,
+    pub pending_shared: Option<Vec<TeamId>>,
+    pub pin_count: Option<u64>,
+    pub previous_names: Option<Vec<String>>,
+    pub priority: Option<f64>,
+    pub purpose: Purpose,
+    pub shared_team_ids: Option<Vec<TeamId>>,
+    pub shares: Option<Vec<Share>>,
+    pub timezone_count: Option<u64>,
+    pub topic: Topic,
+    pub unlinked: Option<u64>,
+    pub unread_count: Option<u64>,
+    pub unread_count_display: Option<u64>,
+    pub use_case: Option<String>,
+    pub user: Option<UserId>,
+    pub version: Option<u64>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
 }
 
+/// Only the fields that tell it apart from other conversations are modelled, so the rest are kept
+/// in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConversationMpim {
+    pub id: ChannelId,
+    #[serde(deserialize_with = "bool_true")]
+    pub is_mpim: bool,
 
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Only the fields that tell it apart from other conversations are modelled, so the rest are kept
+/// in `extra`. Strict parsing doesn't report them
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConversationIm {
+    pub id: ChannelId,
+    #[serde(deserialize_with = "bool_true")]
+    pub is_im: bool,
+    pub user: UserId,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults<T> {
+    pub total: u64,
+    pub matches: Vec<T>,
+    pub paging: Option<Paging>,
+    pub pagination: Option<Pagination>,
 
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> SearchResults<T> {
+    pub fn next_page(&self) -> Option<u64> {
+        self.paging.as_ref().and_then(Paging::next_page)
+    }
+}
+
+/// The channel a search match was found in
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchChannel {
+    pub id: ChannelId,
+    pub name: Option<String>,
+    pub is_channel: Option<bool>,
+    pub is_group: Option<bool>,
+    pub is_im: Option<bool>,
+    pub is_mpim: Option<bool>,
+    pub is_private: Option<bool>,
+    pub is_shared: Option<bool>,
+    pub is_ext_shared: Option<bool>,
+    pub is_org_shared: Option<bool>,
+    pub is_pending_ext_shared: Option<bool>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+/// A message surrounding a search match, included so the match can be read in context
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchContext {
+    pub iid: Option<String>,
+    pub user: Option<UserId>,
+    pub username: Option<String>,
+    pub text: String,
+    pub ts: Timestamp,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageMatch {
+    pub iid: Option<String>,
+    pub team: Option<TeamId>,
+    pub channel: MatchChannel,
+    pub user: Option<UserId>,
+    pub username: Option<String>,
+    pub text: String,
+    pub ts: Timestamp,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub permalink: Option<String>, // TODO: Actually URI
+    pub attachments: Option<Vec<Attachment>>,
+
+    pub previous_2: Option<MatchContext>,
+    pub previous: Option<MatchContext>,
+    pub next: Option<MatchContext>,
+    pub next_2: Option<MatchContext>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug)]
+pub enum SearchMatch {
+    Message(MessageMatch),
+    File(File),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct User {
+    pub id: UserId,
+    pub team_id: Option<TeamId>,
+    pub name: String,
+    pub deleted: Option<bool>,
+    pub real_name: Option<String>,
+    pub tz: Option<String>,
+    pub tz_label: Option<String>,
+    pub tz_offset: Option<i64>,
+    pub color: Option<String>,
+    pub locale: Option<String>,
+
+    pub is_admin: Option<bool>,
+    pub is_owner: Option<bool>,
+    pub is_primary_owner: Option<bool>,
+    pub is_restricted: Option<bool>,
+    pub is_ultra_restricted: Option<bool>,
+    pub is_bot: bool,
+    pub is_app_user: Option<bool>,
+    pub is_email_confirmed: Option<bool>,
+
+    pub has_2fa: Option<bool>,
+    pub updated: Option<u64>,
+    pub profile: Option<UserProfile>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub real_name: Option<String>,
+    pub real_name_normalized: Option<String>,
+    pub display_name: Option<String>,
+    pub display_name_normalized: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub title: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub status_text: Option<String>,
+    pub status_emoji: Option<String>,
+    pub status_expiration: Option<u64>,
+    pub avatar_hash: Option<String>,
+    pub image_24: Option<String>, // TODO: Actually URI
+    pub image_48: Option<String>, // TODO: Actually URI
+    pub image_72: Option<String>, // TODO: Actually URI
+    pub image_192: Option<String>, // TODO: Actually URI
+    pub image_512: Option<String>, // TODO: Actually URI
+    pub bot_id: Option<BotId>,
+    pub api_app_id: Option<AppId>,
+    pub team: Option<TeamId>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsergroupPrefs {
+    pub channels: Vec<ChannelId>,
+    pub groups: Vec<ChannelId>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Usergroup {
+    pub id: UsergroupId,
+    pub team_id: TeamId,
+    pub enterprise_subteam_id: Option<String>,
+    pub name: String,
+    pub handle: String,
+    pub description: Option<String>,
+    pub auto_type: Option<String>,
+
+    pub is_usergroup: Option<bool>,
+    pub is_subteam: Option<bool>,
+    pub is_external: Option<bool>,
+
+    pub created_by: Option<UserId>,
+    pub updated_by: Option<UserId>,
+    pub deleted_by: Option<UserId>,
+    pub date_create: Option<u64>,
+    pub date_update: Option<u64>,
+    pub date_delete: Option<u64>,
+
+    pub prefs: Option<UsergroupPrefs>,
+    pub users: Option<Vec<UserId>>,
+    #[serde(default, deserialize_with = "u64_or_string")]
+    pub user_count: Option<u64>,
+
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    pub extra: Map<String, Value>,
+}
+
+/// The changes needed to turn one user group membership into another
+#[derive(Debug, Default, PartialEq)]
+pub struct UsergroupDiff {
+    pub added: Vec<UserId>,
+    pub removed: Vec<UserId>,
+}
+
+impl UsergroupDiff {
+    pub fn between(current: &[UserId], desired: &[UserId]) -> UsergroupDiff {
+        let current_set: BTreeSet<&UserId> = current.iter().collect();
+        let desired_set: BTreeSet<&UserId> = desired.iter().collect();
+
+        UsergroupDiff {
+            added: desired_set.difference(&current_set).map(|&id| id.clone()).collect(),
+            removed: current_set.difference(&desired_set).map(|&id| id.clone()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usergroup_diff() {
+        let ids = |ids: &[&str]| ids.iter().map(|id| UserId::new(id)).collect::<Vec<_>>();
+
+        let diff = UsergroupDiff::between(&ids(&["U1", "U2", "U3"]), &ids(&["U3", "U4", "U2", "U4"]));
+        assert_eq!(diff.added, ids(&["U4"]));
+        assert_eq!(diff.removed, ids(&["U1"]));
+
+        assert!(UsergroupDiff::between(&ids(&["U1", "U2"]), &ids(&["U2", "U1"])).is_empty());
+    }
+
+    #[test]
+    fn test_bool_false() {
+        #[derive(Deserialize)]
+        struct Flags {
+            #[serde(deserialize_with = "bool_false")]
+            is_mpim: bool,
+        }
+
+        assert!(!serde_json::from_str::<Flags>(r#"{"is_mpim": false}"#).unwrap().is_mpim);
+        assert!(serde_json::from_str::<Flags>(r#"{"is_mpim": true}"#).is_err());
+    }
+
+    #[test]
+    fn test_unmodelled_fields() {
+        let (message, unknown) = collect_unknown_fields(|| serde_json::from_value::<Message>(serde_json::json!({
+            "type": "message",
+            "text": "Hello",
+            "ts": "1610000000.000100",
+            "attachments": [{ "fallback": "Hello" }],
+            "user_profile": { "real_name": "Tester" },
+            "future_field": true,
+        })));
+
+        let message = message.unwrap();
+        assert_eq!(message.attachments.unwrap()[0].extra["fallback"], "Hello");
+        assert_eq!(message.user_profile.unwrap().extra["real_name"], "Tester");
+        assert_eq!(unknown, ["future_field"]);
+    }
+
+    #[test]
+    fn test_message_blocks() {
+        let message = serde_json::from_value::<Message>(serde_json::json!({
+            "type": "message",
+            "user": "U061F7AUR",
+            "text": "Deploy finished",
+            "ts": "1610000000.000200",
+            "blocks": [
+                {
+                    "type": "section",
+                    "block_id": "b1",
+                    "text": { "type": "mrkdwn", "text": "Deploy *finished*" },
+                },
+                { "type": "divider", "block_id": "b2" },
+            ],
+        })).unwrap();
+
+        let blocks = message.blocks.unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].extra["text"]["text"], "Deploy *finished*");
+        assert_eq!(blocks[1].extra["type"], "divider");
+    }
+
+    #[test]
+    fn test_unknown_errors_and_warnings() {
+        assert!(matches!(
+            &Error::from_str(String::from("not_authed,team_not_found"))[..],
+            [Error::NotAuthed, Error::Unknown(name)] if name == "team_not_found",
+        ));
+        assert_eq!(
+            Warning::from_str(String::from("missing_charset,something_new")),
+            [Warning::MissingCharset, Warning::Unknown(String::from("something_new"))],
+        );
+    }
 }
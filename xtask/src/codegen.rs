@@ -0,0 +1,335 @@
+//! Turning the spec into `method_def!` declarations. Methods already defined by hand in
+//! `methods.rs` are left to those definitions, but still get an arm in the `Method!` macro.
+
+use std::fmt::Write;
+
+use proc_macro2::TokenTree;
+use syn::punctuated::Punctuated;
+use syn::{Expr, Item, Lit, Meta, Token};
+
+use crate::spec::{Field, MethodSpec, Schema};
+
+/// Lines longer than this are split, one entry per line
+const MAX_WIDTH: usize = 100;
+
+/// Inputs with a better type than their spec's `string`, by name
+const INPUT_TYPES: &[(&str, &str)] = &[
+    ("attachments", "serde_json::Value"),
+    ("blocks", "serde_json::Value"),
+    ("channel", "ChannelId"),
+    ("file", "FileId"),
+    ("message_ts", "Timestamp"),
+    ("team_id", "TeamId"),
+    ("thread_ts", "Timestamp"),
+    ("timestamp", "Timestamp"),
+    ("ts", "Timestamp"),
+    ("user", "UserId"),
+    ("user_id", "UserId"),
+    ("usergroup", "UsergroupId"),
+    ("view", "serde_json::Value"),
+];
+
+/// Outputs with a better type than their spec's `string`, by name. Fewer than for inputs, since
+/// outputs like `user` are sometimes names rather than IDs
+const OUTPUT_TYPES: &[(&str, &str)] = &[
+    ("bot_id", "BotId"),
+    ("channel", "ChannelId"),
+    ("message_ts", "Timestamp"),
+    ("team_id", "TeamId"),
+    ("ts", "Timestamp"),
+    ("user_id", "UserId"),
+];
+
+/// The crate's types for the spec's object definitions
+const DEFINITION_TYPES: &[(&str, &str)] = &[
+    ("objs_comment", "Comment"),
+    ("objs_conversation", "Conversation"),
+    ("objs_file", "File"),
+    ("objs_message", "Message"),
+    ("objs_paging", "Paging"),
+    ("objs_response_metadata", "ResponseMeta"),
+    ("objs_user", "User"),
+    ("objs_usergroup", "Usergroup"),
+    ("objs_view", "View"),
+];
+
+/// The name of a method's type, such as `ChatPostMessage` for `chat.postMessage`
+pub fn type_name(api: &str) -> String {
+    api.split('.')
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars)
+        })
+        .collect()
+}
+
+/// The methods defined by hand in the source of `methods.rs`, with `method_def!` or
+/// `#[derive(SlackMethod)]`
+pub fn hand_tuned(source: &str) -> syn::Result<Vec<String>> {
+    let file = syn::parse_file(source)?;
+    let mut paths = Vec::new();
+    collect_paths(&file.items, &mut paths)?;
+    Ok(paths)
+}
+
+fn collect_paths(items: &[Item], paths: &mut Vec<String>) -> syn::Result<()> {
+    for item in items {
+        match item {
+            Item::Macro(item) if item.mac.path.is_ident("method_def") => {
+                paths.extend(method_def_paths(item.mac.tokens.clone()));
+            }
+            Item::Struct(item) => {
+                for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("slack")) {
+                    let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+                    paths.extend(metas.iter().filter_map(|meta| match meta {
+                        Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+                            Expr::Lit(expr) => lit_str(&expr.lit),
+                            _ => None,
+                        },
+                        _ => None,
+                    }));
+                }
+            }
+            Item::Mod(item) => {
+                if let Some((_, items)) = &item.content {
+                    collect_paths(items, paths)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The `path: "..."` of each definition in a `method_def!` invocation
+fn method_def_paths(tokens: proc_macro2::TokenStream) -> Vec<String> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    tokens.windows(3)
+        .filter_map(|window| match window {
+            [TokenTree::Ident(ident), TokenTree::Punct(colon), TokenTree::Literal(literal)]
+                if ident == "path" && colon.as_char() == ':' => lit_str(&Lit::new(literal.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn lit_str(lit: &Lit) -> Option<String> {
+    match lit {
+        Lit::Str(lit) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+fn rust_type(field: &Field, is_output: bool) -> String {
+    let named = if is_output { OUTPUT_TYPES } else { INPUT_TYPES };
+    let ty = schema_type(&field.name, &field.schema, named);
+
+    // A missing output is read as null, so optional ones are `Option`s. Inputs are left out instead
+    if is_output && !field.required {
+        format!("Option<{}>", ty)
+    } else {
+        ty
+    }
+}
+
+fn schema_type(name: &str, schema: &Schema, named: &[(&str, &str)]) -> String {
+    if let Some(reference) = &schema.reference {
+        let definition = reference.trim_start_matches("#/definitions/");
+        return DEFINITION_TYPES.iter()
+            .find(|(name, _)| *name == definition)
+            .map_or("serde_json::Value", |(_, ty)| ty)
+            .to_string();
+    }
+
+    match schema.ty.as_deref() {
+        Some("string") => named.iter()
+            .find(|(field, _)| *field == name)
+            .map_or("String", |(_, ty)| ty)
+            .to_string(),
+        Some("boolean") => String::from("bool"),
+        Some("integer") => String::from("u64"),
+        Some("number") => String::from("f64"),
+        Some("array") => {
+            let item = schema.items.as_ref().map_or_else(|| String::from("serde_json::Value"), |items| schema_type("", items, named));
+            format!("Vec<{}>", item)
+        }
+        _ => String::from("serde_json::Value"),
+    }
+}
+
+/// Write a list section of a definition on one line if it fits, and one entry per line if not
+fn write_list(out: &mut String, label: &str, entries: &[String]) {
+    let line = format!("        {}: [{}],", label, entries.join(", "));
+
+    if line.len() <= MAX_WIDTH {
+        writeln!(out, "{}", line).unwrap();
+    } else {
+        writeln!(out, "        {}: [", label).unwrap();
+        for entry in entries {
+            writeln!(out, "            {},", entry).unwrap();
+        }
+        writeln!(out, "        ],").unwrap();
+    }
+}
+
+fn quoted(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+fn write_method(out: &mut String, method: &MethodSpec) {
+    writeln!(out, "method_def! {{").unwrap();
+    writeln!(out, "    {} =>", type_name(&method.name)).unwrap();
+    writeln!(out, "        path: {},", quoted(&method.name)).unwrap();
+
+    if !method.scopes.is_empty() {
+        let scopes = method.scopes.iter().map(|scope| quoted(scope)).collect::<Vec<_>>();
+        write_list(out, "scopes", &scopes);
+    }
+    if let Some(tier) = &method.rate_limit {
+        writeln!(out, "        ratelimit: {},", tier).unwrap();
+    }
+    if method.json {
+        writeln!(out, "        encoding: Json,").unwrap();
+    }
+
+    let has_input = |name| method.inputs.iter().any(|input| input.name == name);
+    if has_input("cursor") {
+        writeln!(out, "        pagination: Cursor,").unwrap();
+    } else if has_input("page") {
+        writeln!(out, "        pagination: Page,").unwrap();
+    }
+
+    if let Some(reason) = &method.deprecated {
+        writeln!(out, "        deprecated: {},", quoted(reason)).unwrap();
+    }
+
+    if !method.inputs.is_empty() {
+        let inputs = method.inputs.iter()
+            .map(|input| format!("{} => {}", quoted(&input.name), rust_type(input, false)))
+            .collect::<Vec<_>>();
+        write_list(out, "inputs", &inputs);
+
+        let required = method.inputs.iter()
+            .filter(|input| input.required)
+            .map(|input| quoted(&input.name))
+            .collect::<Vec<_>>();
+        if !required.is_empty() {
+            write_list(out, "required", &required);
+        }
+    }
+
+    for (label, groups) in [("any_of", &method.any_of), ("one_of", &method.one_of)] {
+        if !groups.is_empty() {
+            let groups = groups.iter()
+                .map(|group| format!("[{}]", group.iter().map(|name| quoted(name)).collect::<Vec<_>>().join(", ")))
+                .collect::<Vec<_>>();
+            write_list(out, label, &groups);
+        }
+    }
+
+    let outputs = method.outputs.iter()
+        .map(|output| format!("{} => {}", quoted(&output.name), rust_type(output, true)))
+        .collect::<Vec<_>>();
+    write_list(out, "outputs", &outputs);
+
+    writeln!(out, "}}").unwrap();
+}
+
+/// Write the `Paginated` impl of a method paged by cursor, which walks its one list output
+fn write_paginated(out: &mut String, method: &MethodSpec) -> Result<(), String> {
+    let lists = method.outputs.iter()
+        .filter(|output| output.name != "response_metadata" && output.schema.ty.as_deref() == Some("array"))
+        .collect::<Vec<_>>();
+    let meta = method.outputs.iter().find(|output| output.name == "response_metadata");
+    let (list, meta) = match (lists.as_slice(), meta) {
+        ([list], Some(meta)) => (*list, meta),
+        _ => return Err(format!("`{}` is paged by cursor, but doesn't have one list output and `response_metadata`", method.name)),
+    };
+
+    // Binds one output of `Self::Return` and ignores the rest, which isn't a tuple if there's only one
+    let pattern = |binding: &Field, name: &str| {
+        let names = method.outputs.iter()
+            .map(|output| if output == binding { name } else { "_" })
+            .collect::<Vec<_>>();
+        if names.len() == 1 { names[0].to_string() } else { format!("({})", names.join(", ")) }
+    };
+    let item = list.schema.items.as_ref()
+        .map_or_else(|| String::from("serde_json::Value"), |items| schema_type("", items, OUTPUT_TYPES));
+
+    writeln!(out).unwrap();
+    writeln!(out, "impl Paginated for {} {{", type_name(&method.name)).unwrap();
+    writeln!(out, "    type Item = {};", item).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    fn next_page({}: &Self::Return) -> Option<NextPage> {{", pattern(meta, "meta")).unwrap();
+    let meta = if meta.required { "meta" } else { "meta.as_ref()?" };
+    writeln!(out, "        {}.next_cursor().map(|cursor| NextPage::Cursor(cursor.to_string()))", meta).unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    fn into_items({}: Self::Return) -> Vec<{}> {{", pattern(list, &list.name), item).unwrap();
+    if list.required {
+        writeln!(out, "        {}", list.name).unwrap();
+    } else {
+        writeln!(out, "        {}.unwrap_or_default()", list.name).unwrap();
+    }
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    Ok(())
+}
+
+/// The contents of `methods/generated.rs`, for the methods in `spec` that aren't hand tuned
+pub fn generate(spec: &[MethodSpec], hand_tuned: &[String]) -> Result<String, String> {
+    let mut out = String::new();
+    writeln!(out, "// Generated by `cargo xtask codegen` from spec/slack_web.json, so don't edit it by hand. To tune").unwrap();
+    writeln!(out, "// a method, move its definition into methods.rs and generate this again").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use super::*;").unwrap();
+
+    for method in spec.iter().filter(|method| !hand_tuned.contains(&method.name)) {
+        writeln!(out).unwrap();
+        write_method(&mut out, method);
+
+        if method.inputs.iter().any(|input| input.name == "cursor") {
+            write_paginated(&mut out, method)?;
+        }
+    }
+
+    let mut names = spec.iter()
+        .map(|method| method.name.as_str())
+        .chain(hand_tuned.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+
+    writeln!(out).unwrap();
+    writeln!(out, "#[macro_export]").unwrap();
+    writeln!(out, "macro_rules! Method {{").unwrap();
+    for name in names {
+        writeln!(out, "    ({}) => {{ $crate::methods::{} }};", quoted(name), type_name(name)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(type_name("chat.postMessage"), "ChatPostMessage");
+        assert_eq!(type_name("files.revokePublicURL"), "FilesRevokePublicURL");
+        assert_eq!(type_name("usergroups.users.list"), "UsergroupsUsersList");
+    }
+
+    #[test]
+    fn test_hand_tuned() {
+        let source = "method_def! {\n    ChatDelete =>\n        path: \"chat.delete\",\n        scopes: [\"chat:write\"],\n}\n";
+        assert_eq!(hand_tuned(source).unwrap(), ["chat.delete"]);
+
+        let source = "#[derive(SlackMethod)]\n#[slack(path = \"pins.add\", scopes(\"pins:write\"))]\npub struct PinsAdd;\n";
+        assert_eq!(hand_tuned(source).unwrap(), ["pins.add"]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Checking the crate's method definitions against the spec. Hand-tuned definitions can give
+//! better types than the spec does, but their scopes, rate limits, inputs and input constraints
+//! should still agree with it.
+
+use std::collections::BTreeSet;
+
+use crate::spec::MethodSpec;
+
+/// A method as the crate defines it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub rate_limit: Option<String>,
+    /// Each input's name, and whether it's required
+    pub inputs: Vec<(String, bool)>,
+    pub any_of: Vec<Vec<String>>,
+    pub one_of: Vec<Vec<String>>,
+    pub deprecated: bool,
+}
+
+/// Input groups, ignoring the order of the groups and of the inputs in each
+fn groups(groups: &[Vec<String>]) -> BTreeSet<BTreeSet<&String>> {
+    groups.iter().map(|group| group.iter().collect()).collect()
+}
+
+/// Everything that differs between the spec and the definitions, one line per difference
+pub fn drift(spec: &[MethodSpec], definitions: &[Definition]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for method in spec {
+        if !definitions.iter().any(|definition| definition.name == method.name) {
+            problems.push(format!("{}: in the spec but not defined, so run `cargo xtask codegen`", method.name));
+        }
+    }
+
+    for definition in definitions {
+        let method = match spec.iter().find(|method| method.name == definition.name) {
+            Some(method) => method,
+            None => {
+                problems.push(format!("{}: defined but not in the spec", definition.name));
+                continue;
+            }
+        };
+        let mut problem = |problem: String| problems.push(format!("{}: {}", definition.name, problem));
+
+        let scopes = definition.scopes.iter().collect::<BTreeSet<_>>();
+        let spec_scopes = method.scopes.iter().collect::<BTreeSet<_>>();
+        if scopes != spec_scopes {
+            problem(format!("needs scopes {:?} but the spec says {:?}", scopes, spec_scopes));
+        }
+
+        if definition.rate_limit != method.rate_limit {
+            problem(format!("has rate limit {:?} but the spec says {:?}", definition.rate_limit, method.rate_limit));
+        }
+
+        if definition.deprecated != method.deprecated.is_some() {
+            problem(format!("deprecated is {} but the spec says {}", definition.deprecated, method.deprecated.is_some()));
+        }
+
+        for input in &method.inputs {
+            match definition.inputs.iter().find(|(name, _)| *name == input.name) {
+                Some((_, required)) if *required != input.required => {
+                    problem(format!("input `{}` has required {} but the spec says {}", input.name, required, input.required));
+                }
+                Some(_) => {}
+                None => problem(format!("input `{}` is in the spec but not defined", input.name)),
+            }
+        }
+
+        for (name, _) in &definition.inputs {
+            if !method.inputs.iter().any(|input| input.name == *name) {
+                problem(format!("input `{}` is defined but not in the spec", name));
+            }
+        }
+
+        for (label, defined, specified) in [("any_of", &definition.any_of, &method.any_of), ("one_of", &definition.one_of, &method.one_of)] {
+            if groups(defined) != groups(specified) {
+                problem(format!("has {} {:?} but the spec says {:?}", label, defined, specified));
+            }
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,37 @@
+//! Development tasks for the crate, run with `cargo xtask`. For now that's generating method
+//! definitions from the vendored copy of Slack's Web API spec.
+
+pub mod codegen;
+pub mod drift;
+pub mod spec;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// The spec, relative to the root of the repo
+pub const SPEC: &str = "spec/slack_web.json";
+/// Where the hand-tuned method definitions live
+pub const HAND_TUNED: &str = "src/methods.rs";
+/// Where the generated method definitions go
+pub const GENERATED: &str = "src/methods/generated.rs";
+
+/// The root of the repo, which holds the crate this generates code for
+pub fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the repo")
+        .to_path_buf()
+}
+
+/// Generate the contents of [`GENERATED`] from the spec and the hand-tuned definitions
+pub fn generate() -> Result<String, String> {
+    let root = root();
+    let spec = spec::load(&root.join(SPEC))?;
+    let source = fs::read_to_string(root.join(HAND_TUNED))
+        .map_err(|err| format!("Couldn't read {}: {}", HAND_TUNED, err))?;
+
+    let hand_tuned = codegen::hand_tuned(&source)
+        .map_err(|err| format!("Couldn't parse {}: {}", HAND_TUNED, err))?;
+
+    codegen::generate(&spec, &hand_tuned)
+}
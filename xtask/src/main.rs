@@ -0,0 +1,45 @@
+use std::fs;
+use std::process;
+
+const USAGE: &str = "\
+Usage: cargo xtask <task>
+
+Tasks:
+    codegen          Generate src/methods/generated.rs from spec/slack_web.json
+    codegen --check  Fail if src/methods/generated.rs is out of date";
+
+fn codegen(check: bool) -> Result<(), String> {
+    let generated = xtask::generate()?;
+    let path = xtask::root().join(xtask::GENERATED);
+
+    if check {
+        let current = fs::read_to_string(&path).unwrap_or_default();
+        if current != generated {
+            return Err(format!("{} is out of date, so run `cargo xtask codegen`", xtask::GENERATED));
+        }
+    } else {
+        fs::write(&path, generated)
+            .map_err(|err| format!("Couldn't write {}: {}", xtask::GENERATED, err))?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let result = match args.as_slice() {
+        ["codegen"] => codegen(false),
+        ["codegen", "--check"] => codegen(true),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
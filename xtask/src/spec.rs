@@ -0,0 +1,168 @@
+//! Reading Slack's Web API spec. It's OpenAPI 2.0, with each method as a path like
+//! `/chat.postMessage`. Only the parts we generate from are modelled; the rest is ignored.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Spec {
+    paths: BTreeMap<String, PathItem>,
+}
+
+#[derive(Deserialize)]
+struct PathItem {
+    get: Option<Operation>,
+    post: Option<Operation>,
+}
+
+#[derive(Deserialize)]
+struct Operation {
+    #[serde(default)]
+    security: Vec<BTreeMap<String, Vec<String>>>,
+    /// Not part of Slack's published spec: the tier from each method's docs, added when vendoring
+    #[serde(rename = "x-slack-rate-limit")]
+    rate_limit: Option<String>,
+    #[serde(default)]
+    consumes: Vec<String>,
+    #[serde(default)]
+    parameters: Vec<Parameter>,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(rename = "x-slack-deprecation")]
+    deprecation: Option<String>,
+    /// Not part of Slack's published spec: groups of optional inputs where at least one must be
+    /// given, which OpenAPI 2.0 can't express
+    #[serde(rename = "x-slack-any-of", default)]
+    any_of: Vec<Vec<String>>,
+    /// Not part of Slack's published spec: groups of optional inputs where exactly one must be given
+    #[serde(rename = "x-slack-one-of", default)]
+    one_of: Vec<Vec<String>>,
+    #[serde(default)]
+    responses: BTreeMap<String, Response>,
+}
+
+#[derive(Deserialize)]
+struct Parameter {
+    name: String,
+    #[serde(rename = "in")]
+    location: String,
+    #[serde(default)]
+    required: bool,
+    #[serde(flatten)]
+    schema: Schema,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    schema: Option<Schema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Schema {
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    pub items: Option<Box<Schema>>,
+    #[serde(default)]
+    properties: BTreeMap<String, Schema>,
+    #[serde(default)]
+    required: Vec<String>,
+}
+
+/// One of a method's inputs or outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub required: bool,
+    pub schema: Schema,
+}
+
+/// A method as the spec describes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSpec {
+    /// The API name, such as `chat.postMessage`
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub rate_limit: Option<String>,
+    /// Whether the method takes a JSON body rather than a form
+    pub json: bool,
+    pub inputs: Vec<Field>,
+    pub any_of: Vec<Vec<String>>,
+    pub one_of: Vec<Vec<String>>,
+    /// Everything in a successful response, apart from `ok`
+    pub outputs: Vec<Field>,
+    pub deprecated: Option<String>,
+}
+
+/// Every method in the spec at `path`, sorted by name
+pub fn load(path: &Path) -> Result<Vec<MethodSpec>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+    let spec: Spec = serde_json::from_str(&contents)
+        .map_err(|err| format!("Couldn't parse {}: {}", path.display(), err))?;
+
+    spec.paths
+        .into_iter()
+        .map(|(path, item)| {
+            let name = path.trim_start_matches('/').to_string();
+            let operation = item.post.or(item.get)
+                .ok_or_else(|| format!("`{}` has no GET or POST operation", name))?;
+
+            Ok(method_spec(name, operation))
+        })
+        .collect()
+}
+
+fn method_spec(name: String, operation: Operation) -> MethodSpec {
+    let mut scopes = Vec::new();
+    for scope in operation.security.into_iter().flat_map(|scheme| scheme.into_values().flatten()) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    // The token is sent as a header, so it isn't an input
+    let inputs = operation.parameters
+        .into_iter()
+        .filter(|param| param.location != "header" && param.name != "token")
+        .map(|param| Field { name: param.name, required: param.required, schema: param.schema })
+        .collect();
+
+    let outputs = operation.responses
+        .get("200")
+        .and_then(|response| response.schema.as_ref())
+        .map(|schema| {
+            schema.properties
+                .iter()
+                .filter(|(name, _)| *name != "ok")
+                .map(|(name, property)| Field {
+                    name: name.clone(),
+                    required: schema.required.contains(name),
+                    schema: property.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let deprecated = if operation.deprecated {
+        Some(operation.deprecation.unwrap_or_else(|| String::from("Deprecated by Slack")))
+    } else {
+        None
+    };
+
+    MethodSpec {
+        name,
+        scopes,
+        rate_limit: operation.rate_limit,
+        json: operation.consumes.iter().any(|mime| mime == "application/json"),
+        inputs,
+        any_of: operation.any_of,
+        one_of: operation.one_of,
+        outputs,
+        deprecated,
+    }
+}
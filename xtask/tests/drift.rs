@@ -0,0 +1,42 @@
+//! Keeps the crate in step with the vendored spec: the generated methods have to be up to date,
+//! and hand-tuned definitions can't quietly disagree with Slack about scopes, rate limits, inputs
+//! or input constraints
+
+use std::fs;
+
+use xtask::drift::{drift, Definition};
+
+#[test]
+fn test_generated_is_current() {
+    let current = fs::read_to_string(xtask::root().join(xtask::GENERATED)).unwrap();
+    assert!(current == xtask::generate().unwrap(), "{} is out of date, so run `cargo xtask codegen`", xtask::GENERATED);
+}
+
+fn groups(groups: &[&[&str]]) -> Vec<Vec<String>> {
+    groups.iter().map(|group| group.iter().map(|name| name.to_string()).collect()).collect()
+}
+
+#[test]
+fn test_no_drift() {
+    let spec = xtask::spec::load(&xtask::root().join(xtask::SPEC)).unwrap();
+    let definitions = idle::registry::methods()
+        .into_iter()
+        .map(|method| Definition {
+            name: method.name.to_string(),
+            scopes: method.scopes.iter().map(|scope| scope.to_string()).collect(),
+            rate_limit: method.rate_limit.map(|tier| format!("{:?}", tier)),
+            inputs: method.params.iter().map(|param| (param.name.to_string(), param.required)).collect(),
+            any_of: groups(method.any_of),
+            one_of: groups(method.one_of),
+            deprecated: method.deprecated.is_some(),
+        })
+        .collect::<Vec<_>>();
+
+    // Every scope has to be one Slack defines, or the registry would panic on looking it up
+    for method in idle::registry::methods() {
+        method.scopes();
+    }
+
+    let problems = drift(&spec, &definitions);
+    assert!(problems.is_empty(), "Definitions have drifted from the spec:\n{}", problems.join("\n"));
+}